@@ -0,0 +1,102 @@
+use crate::repo::findings::CheckKind;
+use crate::repo::scan::repo_key;
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A finding that is known and accepted until a given date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Acknowledgement {
+    pub repo: String,
+    pub check: String,
+    pub until: NaiveDate,
+    pub reason: String,
+    pub created: NaiveDate,
+}
+
+impl Acknowledgement {
+    pub fn is_active(&self, today: NaiveDate) -> bool {
+        self.until >= today
+    }
+}
+
+/// Acknowledgements persisted in a TOML state file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AckStore {
+    #[serde(default, rename = "ack")]
+    acks: Vec<Acknowledgement>,
+}
+
+impl AckStore {
+    /// Default location under the XDG data directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("gitgud").join("acks.toml"))
+    }
+
+    /// Load the state file, a missing file means no acknowledgements
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Add or replace the acknowledgement for a repository and check
+    pub fn acknowledge(
+        &mut self,
+        repo_path: &Path,
+        check: CheckKind,
+        until: NaiveDate,
+        reason: String,
+    ) {
+        self.remove(repo_path, check);
+        self.acks.push(Acknowledgement {
+            repo: repo_key(repo_path),
+            check: check.id().to_string(),
+            until,
+            reason,
+            created: Local::now().date_naive(),
+        });
+    }
+
+    /// Returns whether anything was removed
+    pub fn remove(&mut self, repo_path: &Path, check: CheckKind) -> bool {
+        let repo = repo_key(repo_path);
+        let before = self.acks.len();
+        self.acks
+            .retain(|ack| !(ack.repo == repo && ack.check == check.id()));
+        self.acks.len() != before
+    }
+
+    /// Drop acknowledgements that expired before today
+    pub fn prune_expired(&mut self) -> usize {
+        let today = Local::now().date_naive();
+        let before = self.acks.len();
+        self.acks.retain(|ack| ack.is_active(today));
+        before - self.acks.len()
+    }
+
+    /// Acknowledgements for a repository that have not expired yet
+    pub fn active_for(&self, repo_path: &Path) -> HashMap<CheckKind, Acknowledgement> {
+        let repo = repo_key(repo_path);
+        let today = Local::now().date_naive();
+
+        self.acks
+            .iter()
+            .filter(|ack| ack.repo == repo && ack.is_active(today))
+            .filter_map(|ack| Some((ack.check.parse().ok()?, ack.clone())))
+            .collect()
+    }
+}
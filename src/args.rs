@@ -1,10 +1,148 @@
-use clap::Parser;
+use crate::config::GithubBackend;
+use crate::repo::findings::CheckKind;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+/// Output format for scan results
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored human readable output
+    Text,
+    /// JUnit XML, one testcase per repository per check
+    Junit,
+    /// SARIF 2.1.0, one result per finding
+    Sarif,
+    /// Prometheus text exposition format
+    Prometheus,
+    /// Stable tab separated `path, check, detail` lines, one per finding
+    Porcelain,
+}
+
+/// When to emit colors and other terminal escape sequences
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Browse and triage the scanned repositories in an interactive terminal UI
+    Tui,
+
+    /// Keep watching the repositories and re-check them whenever they change
+    Watch {
+        /// Emit one JSON change event per line instead of redrawing the report
+        #[arg(long)]
+        ndjson: bool,
+
+        /// Milliseconds to wait for further changes before re-checking
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
+    },
+
+    /// Show how long each check has been failing or passing for a repository
+    History {
+        /// Repository path or directory name
+        repo: String,
+    },
+
+    /// Accept a finding until a date, it is no longer reported as an issue until then
+    Ack {
+        /// Repository path
+        repo: PathBuf,
+
+        /// Check to acknowledge (untracked, unstaged, ahead, no-remote, branch, open-prs, fetch-failed,
+        /// misplaced, behind-upstream, merged-upstream, upstream-prs, remote-health, rate-limited)
+        check: CheckKind,
+
+        /// Last day the acknowledgement is valid (YYYY-MM-DD)
+        #[arg(long)]
+        until: NaiveDate,
+
+        /// Why the finding is acceptable for now
+        #[arg(long, default_value = "")]
+        reason: String,
+    },
+
+    /// Remove an acknowledgement before it expires
+    Unack {
+        /// Repository path
+        repo: PathBuf,
+
+        /// Check to stop acknowledging
+        check: CheckKind,
+    },
+
+    /// Show failing check counts per week from the scan history
+    Trend {
+        /// Number of weeks to report
+        #[arg(long, default_value_t = 12)]
+        weeks: u32,
+    },
+
+    /// Propose safe git commands that resolve findings, and run them when asked to
+    Fix {
+        /// Only print the commands that would run (the default)
+        #[arg(long, conflicts_with_all = ["apply", "interactive"])]
+        dry_run: bool,
+
+        /// Run the fixes for every repository without asking
+        #[arg(long, conflicts_with = "interactive")]
+        apply: bool,
+
+        /// Ask before running the fixes for each repository
+        #[arg(short, long)]
+        interactive: bool,
+    },
+
+    /// Fast-forward every clean repository that is on its default branch
+    Pull,
+
+    /// Clone the repositories from the workspace manifest that are missing
+    Sync {
+        /// Workspace manifest to use instead of the one from the config
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+    },
+
+    /// Move repositories into the canonical <directory>/<host>/<owner>/<repo> layout
+    Relocate {
+        /// Move the repositories instead of only printing where they would go
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// List remotes that are cloned more than once and what each copy holds
+    Duplicates,
+
+    /// Save unpushed commits, stashes and uncommitted files into a dated directory
+    Backup {
+        /// Directory the dated backup directory is created in
+        dest: PathBuf,
+    },
+
+    /// Bring back the work saved by `backup`
+    Restore {
+        /// Dated backup directory containing manifest.json
+        backup: PathBuf,
+
+        /// Restore below this directory instead of the original locations
+        #[arg(long)]
+        into: Option<PathBuf>,
+    },
+}
+
 /// Opinionated Git repository scanner to keep you Organized and On Task
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Directory containing Git repositories to scan
     #[arg(default_value = ".")]
     pub directory: PathBuf,
@@ -17,14 +155,92 @@ pub struct Args {
     #[arg(short, long, env = "GITGUD_THREADS")]
     pub threads: Option<usize>,
 
+    /// Print nothing, only report through the exit code (1 when issues were found)
+    #[arg(short, long, conflicts_with = "porcelain")]
+    pub quiet: bool,
+
+    /// Print one stable tab separated line per finding (same as --format porcelain)
+    #[arg(long)]
+    pub porcelain: bool,
+
     /// Show all repositories, even those with no issues
     #[arg(short, long)]
     pub all: bool,
 
+    /// Output format for scan results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, env = "GITGUD_FORMAT")]
+    pub format: OutputFormat,
+
+    /// When to use colors in the output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, env = "GITGUD_COLOR")]
+    pub color: ColorChoice,
+
+    /// Emit OSC 8 terminal hyperlinks for repository paths and pull requests
+    #[arg(long, env = "GITGUD_HYPERLINKS")]
+    pub hyperlinks: bool,
+
+    /// Output template for each repository, inline or `@path` to read it from a file
+    #[arg(long, env = "GITGUD_TEMPLATE")]
+    pub template: Option<String>,
+
+    /// Also write Prometheus metrics to this file (for the node_exporter textfile collector)
+    #[arg(long, env = "GITGUD_WRITE_TEXTFILE")]
+    pub write_textfile: Option<PathBuf>,
+
+    /// Don't read or write the scan cache
+    #[arg(long, conflicts_with = "refresh")]
+    pub no_cache: bool,
+
+    /// Ignore the scan cache for this run, but update it with fresh results
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// Only show what changed since the previous recorded scan of this directory,
+    /// other formats than text only report the repositories that changed
+    #[arg(long, conflicts_with_all = ["quiet", "template"])]
+    pub since_last: bool,
+
+    /// Don't record this scan in the history database
+    #[arg(long, env = "GITGUD_NO_HISTORY", conflicts_with = "record_history")]
+    pub no_history: bool,
+
+    /// Record this scan in the history database even when it only exports results,
+    /// only text output and --since-last runs are recorded by default
+    #[arg(long, env = "GITGUD_RECORD_HISTORY")]
+    pub record_history: bool,
+
     /// Path to config file (default: ~/.gitgud.toml)
     #[arg(short, long)]
     pub config: Option<PathBuf>,
 
+    /// Fetch (and prune) all remotes of every repository before checking
+    #[arg(long, env = "GITGUD_FETCH")]
+    pub fetch: bool,
+
+    /// Maximum number of repositories fetched at the same time
+    #[arg(long, default_value_t = 8, env = "GITGUD_FETCH_JOBS")]
+    pub fetch_jobs: usize,
+
+    /// Give up fetching a repository after this long (e.g. 30s, 2m)
+    #[arg(long, value_parser = crate::config::parse_duration, default_value = "60s", env = "GITGUD_FETCH_TIMEOUT")]
+    pub fetch_timeout: u64,
+
+    /// Expect repositories at <directory>/<host>/<owner>/<repo> and flag the ones elsewhere
+    #[arg(long, env = "GITGUD_CANONICAL_LAYOUT")]
+    pub canonical_layout: bool,
+
+    /// Check that remotes answer and GitHub repositories weren't moved, archived or deleted
+    #[arg(long, env = "GITGUD_CHECK_REMOTE_HEALTH")]
+    pub check_remote_health: bool,
+
+    /// Remote to check ahead commits and pull requests against (default: origin)
+    #[arg(long, env = "GITGUD_PRIMARY_REMOTE")]
+    pub primary_remote: Option<String>,
+
+    /// Remote that points at the repository a fork was made from (default: upstream)
+    #[arg(long, env = "GITGUD_UPSTREAM_REMOTE")]
+    pub upstream_remote: Option<String>,
+
     /// Check for untracked files
     #[arg(long, action = clap::ArgAction::Set, default_value_t = true, env = "GITGUD_CHECK_UNTRACKED")]
     pub check_untracked: bool,
@@ -45,6 +261,10 @@ pub struct Args {
     #[arg(long, action = clap::ArgAction::Set, default_value_t = true, env = "GITGUD_CHECK_BRANCH")]
     pub check_branch: bool,
 
+    /// Only flag untracked/unstaged changes uncommitted this long (e.g. 3d, 12h, 0 for all)
+    #[arg(long, value_parser = crate::config::parse_duration, env = "GITGUD_DIRTY_WARN_AFTER")]
+    pub dirty_warn_after: Option<u64>,
+
     /// Check for open GitHub pull requests
     #[arg(long, action = clap::ArgAction::Set, default_value_t = false, env = "GITGUD_CHECK_PRS")]
     pub check_prs: bool,
@@ -53,6 +273,18 @@ pub struct Args {
     #[arg(long, action = clap::ArgAction::Set, default_value_t = true, env = "GITGUD_INCLUDE_DRAFT_PRS")]
     pub include_draft_prs: bool,
 
+    /// For forks, check upstream: default branch behind, branches merged there, our PRs to it
+    #[arg(long, action = clap::ArgAction::Set, default_value_t = false, env = "GITGUD_CHECK_FORK")]
+    pub check_fork: bool,
+
+    /// Most open PRs listed per repository, the rest are only counted (default: 100)
+    #[arg(long, env = "GITGUD_MAX_PRS")]
+    pub max_prs: Option<usize>,
+
+    /// API used for pull request lookups (default: rest)
+    #[arg(long, value_enum, env = "GITGUD_GITHUB_BACKEND")]
+    pub github_backend: Option<GithubBackend>,
+
     /// GitHub token (or set GITHUB_TOKEN env var)
     #[arg(long, env = "GITHUB_TOKEN")]
     pub github_token: Option<String>,
@@ -0,0 +1,565 @@
+use crate::display::path_link;
+use crate::git::branches::unpushed_branches;
+use crate::git::remote::{list_remotes, Remote};
+use crate::git::stash::{list_stashes, StashEntry};
+use crate::repo::scan::repo_key;
+use chrono::Local;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const BUNDLE_FILE: &str = "unpushed.bundle";
+const PATCH_FILE: &str = "uncommitted.patch";
+const UNTRACKED_FILE: &str = "untracked.tar.gz";
+
+/// Temporary refs that carry stash entries through a bundle
+const STASH_REF_PREFIX: &str = "refs/gitgud-backup/stash-";
+/// Where restored branches that can't be fast-forwarded are kept
+const RESTORED_REF_PREFIX: &str = "refs/gitgud-backup/heads/";
+
+/// Everything written by one `gitgud backup` run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub created: String,
+    pub root: String,
+    pub repos: Vec<RepoBackup>,
+}
+
+/// What was saved for a single repository, file names are relative to `dir`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoBackup {
+    pub path: String,
+    pub dir: String,
+    /// Checked out branch, `HEAD` when detached
+    pub head: String,
+    pub head_commit: Option<String>,
+    pub remotes: Vec<Remote>,
+    pub bundle: Option<String>,
+    /// Branches in the bundle with commits not on any remote
+    pub branches: Vec<String>,
+    /// Stash entries in the bundle, newest first
+    pub stashes: Vec<StashEntry>,
+    /// `git diff HEAD` of staged and unstaged changes
+    pub patch: Option<String>,
+    pub untracked: Option<String>,
+    pub untracked_files: Vec<String>,
+}
+
+/// Run git in `repo_path` and return its raw stdout
+fn git_output(repo_path: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| format!("Unable to run git: {}", e))?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Run git in `repo_path` and return its trimmed stdout
+fn git(repo_path: &Path, args: &[&str]) -> Result<String, String> {
+    let stdout = git_output(repo_path, args)?;
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+fn untracked_files(repo_path: &Path) -> Vec<String> {
+    // NUL separated so unusual file names come through unquoted
+    let stdout = git_output(
+        repo_path,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )
+    .unwrap_or_default();
+    String::from_utf8_lossy(&stdout)
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Bundle the unpushed branches and all stash entries of a repository
+fn write_bundle(
+    repo_path: &Path,
+    bundle_path: &Path,
+    branches: &[String],
+    stashes: &[StashEntry],
+) -> Result<(), String> {
+    // Bundles only carry refs, so give every stash entry one for the moment
+    for (index, stash) in stashes.iter().enumerate() {
+        let name = format!("{}{}", STASH_REF_PREFIX, index);
+        git(repo_path, &["update-ref", &name, &stash.commit])?;
+    }
+
+    let bundle = bundle_path.display().to_string();
+    let mut args = vec!["bundle".to_string(), "create".to_string(), bundle];
+    args.extend(
+        branches
+            .iter()
+            .map(|branch| format!("refs/heads/{}", branch)),
+    );
+    args.extend((0..stashes.len()).map(|index| format!("{}{}", STASH_REF_PREFIX, index)));
+    args.extend(["--not".to_string(), "--remotes".to_string()]);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = git(repo_path, &args).map(|_| ());
+
+    for index in 0..stashes.len() {
+        let name = format!("{}{}", STASH_REF_PREFIX, index);
+        let _ = git(repo_path, &["update-ref", "-d", &name]);
+    }
+
+    result
+}
+
+fn write_untracked_tarball(
+    repo_path: &Path,
+    tarball: &Path,
+    files: &[String],
+) -> Result<(), String> {
+    let mut child = Command::new("tar")
+        .arg("-czf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(repo_path)
+        .arg("--null")
+        .arg("-T")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Unable to run tar: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        for file in files {
+            stdin
+                .write_all(file.as_bytes())
+                .and_then(|_| stdin.write_all(b"\0"))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Save the unpushed and uncommitted work of one repository into `backup_dir`
+///
+/// Returns `None` when there was nothing worth saving.
+fn backup_repo(
+    repo_path: &Path,
+    backup_dir: &Path,
+    dir: &str,
+) -> Result<Option<RepoBackup>, Box<dyn std::error::Error>> {
+    let branches: Vec<String> = unpushed_branches(repo_path)
+        .into_iter()
+        .map(|(branch, _)| branch)
+        .collect();
+    let stashes = list_stashes(repo_path);
+    let head_commit = git(repo_path, &["rev-parse", "--verify", "HEAD"]).ok();
+    // An unborn branch has nothing to diff against, its files are all untracked
+    let patch = match head_commit {
+        Some(_) => git_output(repo_path, &["diff", "--binary", "HEAD"])?,
+        None => Vec::new(),
+    };
+    let untracked = untracked_files(repo_path);
+
+    if branches.is_empty() && stashes.is_empty() && patch.is_empty() && untracked.is_empty() {
+        return Ok(None);
+    }
+
+    let repo_dir = backup_dir.join(dir);
+    fs::create_dir_all(&repo_dir)?;
+
+    let bundle = if branches.is_empty() && stashes.is_empty() {
+        None
+    } else {
+        write_bundle(repo_path, &repo_dir.join(BUNDLE_FILE), &branches, &stashes)?;
+        Some(BUNDLE_FILE.to_string())
+    };
+
+    let patch = if patch.is_empty() {
+        None
+    } else {
+        fs::write(repo_dir.join(PATCH_FILE), &patch)?;
+        Some(PATCH_FILE.to_string())
+    };
+
+    let untracked_tarball = if untracked.is_empty() {
+        None
+    } else {
+        write_untracked_tarball(repo_path, &repo_dir.join(UNTRACKED_FILE), &untracked)?;
+        Some(UNTRACKED_FILE.to_string())
+    };
+
+    Ok(Some(RepoBackup {
+        path: repo_key(repo_path),
+        dir: dir.to_string(),
+        head: git(repo_path, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default(),
+        head_commit,
+        remotes: list_remotes(repo_path),
+        bundle,
+        branches,
+        stashes,
+        patch,
+        untracked: untracked_tarball,
+        untracked_files: untracked,
+    }))
+}
+
+/// Directory name for a repository inside the backup, unique among `taken`
+fn backup_dir_name(repo_path: &Path, taken: &mut HashSet<String>) -> String {
+    let base = repo_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while !taken.insert(name.clone()) {
+        name = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+fn describe_backup(backup: &RepoBackup) -> String {
+    let mut saved = Vec::new();
+    if !backup.branches.is_empty() {
+        saved.push(format!("{} unpushed branches", backup.branches.len()));
+    }
+    if !backup.stashes.is_empty() {
+        saved.push(format!("{} stashes", backup.stashes.len()));
+    }
+    if backup.patch.is_some() {
+        saved.push("uncommitted changes".to_string());
+    }
+    if !backup.untracked_files.is_empty() {
+        saved.push(format!("{} untracked files", backup.untracked_files.len()));
+    }
+    saved.join(", ")
+}
+
+/// Back up every repository with unpushed or uncommitted work into a dated directory below `dest`
+pub fn run_backup(
+    root: &Path,
+    repos: &[PathBuf],
+    dest: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let created = Local::now();
+    // Git runs inside each repository, so every path handed to it must be absolute
+    fs::create_dir_all(dest)?;
+    let backup_dir = fs::canonicalize(dest)?.join(format!(
+        "gitgud-backup-{}",
+        created.format("%Y-%m-%d-%H%M%S")
+    ));
+    fs::create_dir_all(&backup_dir)?;
+
+    let mut taken = HashSet::new();
+    let mut backups = Vec::new();
+    let mut failed = 0;
+
+    for repo_path in repos {
+        let path = path_link(repo_path);
+        let dir = backup_dir_name(repo_path, &mut taken);
+
+        match backup_repo(repo_path, &backup_dir, &dir) {
+            Ok(Some(backup)) => {
+                println!(
+                    "{}",
+                    format!("[+] {} => saved {}", path, describe_backup(&backup))
+                        .green()
+                        .bold()
+                );
+                backups.push(backup);
+            }
+            Ok(None) => {
+                taken.remove(&dir);
+            }
+            Err(e) => {
+                println!(
+                    "{}",
+                    format!("[!] {} => backup failed: {}", path, e).red().bold()
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    let manifest = Manifest {
+        created: created.to_rfc3339(),
+        root: repo_key(root),
+        repos: backups,
+    };
+    fs::write(
+        backup_dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    println!();
+    println!(
+        "Backup complete: {} repositories saved to {}",
+        manifest.repos.len(),
+        backup_dir.display()
+    );
+
+    if failed > 0 {
+        return Err(format!("{} repositories could not be backed up", failed).into());
+    }
+    Ok(backup_dir)
+}
+
+/// Clone or create the repository a backup belongs to
+fn recreate_repo(target: &Path, backup: &RepoBackup) -> Result<(), String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let origin = backup
+        .remotes
+        .iter()
+        .find(|remote| remote.name == "origin")
+        .or(backup.remotes.first());
+
+    match origin {
+        Some(remote) => {
+            let target_arg = target.display().to_string();
+            git(
+                Path::new("."),
+                &["clone", "--origin", &remote.name, &remote.url, &target_arg],
+            )?;
+        }
+        None if backup.bundle.is_some() => {
+            // Without remotes nothing was excluded, the bundle holds the full history
+            git(
+                Path::new("."),
+                &["init", "--quiet", &target.display().to_string()],
+            )?;
+        }
+        None => return Err("no remote to clone from and no bundle".to_string()),
+    }
+
+    let cloned_from = origin.map(|remote| remote.name.as_str());
+    for remote in &backup.remotes {
+        if Some(remote.name.as_str()) != cloned_from {
+            git(target, &["remote", "add", &remote.name, &remote.url])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Bring back the branches and stashes from a bundle
+fn restore_bundle(target: &Path, backup: &RepoBackup, bundle: &Path) -> Vec<String> {
+    let mut notes = Vec::new();
+    let bundle = bundle.display().to_string();
+
+    let mut refspecs = vec!["fetch".to_string(), "--quiet".to_string(), bundle];
+    refspecs.extend(
+        backup
+            .branches
+            .iter()
+            .map(|branch| format!("+refs/heads/{0}:{1}{0}", branch, RESTORED_REF_PREFIX)),
+    );
+    refspecs.extend((0..backup.stashes.len()).map(|index| {
+        let name = format!("{}{}", STASH_REF_PREFIX, index);
+        format!("+{0}:{0}", name)
+    }));
+    let args: Vec<&str> = refspecs.iter().map(String::as_str).collect();
+    if let Err(e) = git(target, &args) {
+        notes.push(format!("unable to read bundle: {}", e));
+        return notes;
+    }
+
+    let current = git(target, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+    for branch in &backup.branches {
+        let saved = format!("{}{}", RESTORED_REF_PREFIX, branch);
+        let local = format!("refs/heads/{}", branch);
+
+        let restored = if git(target, &["rev-parse", "--verify", "--quiet", &local]).is_err() {
+            git(target, &["branch", branch, &saved]).is_ok()
+        } else if git(target, &["merge-base", "--is-ancestor", &local, &saved]).is_ok() {
+            if *branch == current {
+                git(target, &["merge", "--ff-only", "--quiet", &saved]).is_ok()
+            } else {
+                git(target, &["update-ref", &local, &saved]).is_ok()
+            }
+        } else {
+            false
+        };
+
+        if restored {
+            let _ = git(target, &["update-ref", "-d", &saved]);
+        } else {
+            notes.push(format!("{} diverged, kept as {}", branch, saved));
+        }
+    }
+
+    // Oldest first so the stash ends up in its original order
+    let existing: HashSet<String> = list_stashes(target)
+        .into_iter()
+        .map(|stash| stash.commit)
+        .collect();
+    for (index, stash) in backup.stashes.iter().enumerate().rev() {
+        let name = format!("{}{}", STASH_REF_PREFIX, index);
+        if existing.contains(&stash.commit) {
+            let _ = git(target, &["update-ref", "-d", &name]);
+            continue;
+        }
+        match git(
+            target,
+            &["stash", "store", "-m", &stash.message, &stash.commit],
+        ) {
+            Ok(_) => {
+                let _ = git(target, &["update-ref", "-d", &name]);
+            }
+            Err(e) => notes.push(format!("stash {} not restored: {}", stash.commit, e)),
+        }
+    }
+
+    notes
+}
+
+fn restore_repo(
+    backup: &RepoBackup,
+    backup_dir: &Path,
+    target: &Path,
+) -> Result<Vec<String>, String> {
+    let repo_dir = backup_dir.join(&backup.dir);
+    let mut notes = Vec::new();
+
+    let fresh = !target.join(".git").exists();
+    if fresh {
+        recreate_repo(target, backup)?;
+    }
+
+    if let Some(bundle) = &backup.bundle {
+        notes.extend(restore_bundle(target, backup, &repo_dir.join(bundle)));
+    }
+
+    if fresh && backup.head != "HEAD" {
+        if let Err(e) = git(target, &["switch", "--quiet", &backup.head]) {
+            notes.push(format!("unable to check out {}: {}", backup.head, e));
+        }
+    }
+
+    if let Some(patch) = &backup.patch {
+        let head = git(target, &["rev-parse", "--verify", "HEAD"]).ok();
+        if head != backup.head_commit {
+            notes.push("HEAD moved since the backup, applying the patch anyway".to_string());
+        }
+
+        let patch = repo_dir.join(patch).display().to_string();
+        if let Err(e) = git(target, &["apply", "--whitespace=nowarn", &patch]) {
+            notes.push(format!("uncommitted changes not applied: {}", e));
+        }
+    }
+
+    if let Some(tarball) = &backup.untracked {
+        // Never overwrite files that exist in the target
+        let output = Command::new("tar")
+            .arg("-xzkf")
+            .arg(repo_dir.join(tarball))
+            .arg("-C")
+            .arg(target)
+            .output()
+            .map_err(|e| format!("Unable to run tar: {}", e))?;
+        if !output.status.success() {
+            notes.push(format!(
+                "some untracked files not restored: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+
+    Ok(notes)
+}
+
+/// Restore every repository from a backup directory written by `run_backup`
+///
+/// Repositories go back to their original paths, or below `into` when given.
+pub fn run_restore(
+    backup_dir: &Path,
+    into: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest_path = backup_dir.join(MANIFEST_FILE);
+    let manifest: Manifest = serde_json::from_str(
+        &fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Unable to read {}: {}", manifest_path.display(), e))?,
+    )?;
+
+    // Git runs inside each target, so every path handed to it must be absolute
+    let backup_dir = &fs::canonicalize(backup_dir)?;
+    let into = into.map(std::path::absolute).transpose()?;
+    let into = into.as_deref();
+
+    let mut failed = 0;
+    let mut partial = 0;
+    for backup in &manifest.repos {
+        let target = match into {
+            Some(into) => into.join(&backup.dir),
+            None => PathBuf::from(&backup.path),
+        };
+        let path = path_link(&target);
+
+        match restore_repo(backup, backup_dir, &target) {
+            Ok(notes) if notes.is_empty() => {
+                println!(
+                    "{}",
+                    format!("[+] {} => restored {}", path, describe_backup(backup))
+                        .green()
+                        .bold()
+                );
+            }
+            Ok(notes) => {
+                println!(
+                    "{}",
+                    format!("[~] {} => partially restored", path)
+                        .yellow()
+                        .bold()
+                );
+                for note in notes {
+                    println!("     {}", note.replace('\n', "\n     "));
+                }
+                partial += 1;
+            }
+            Err(e) => {
+                println!(
+                    "{}",
+                    format!("[!] {} => restore failed: {}", path, e)
+                        .red()
+                        .bold()
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Restore complete: {} repositories from {}",
+        manifest.repos.len() - failed,
+        backup_dir.display()
+    );
+
+    if failed > 0 || partial > 0 {
+        return Err(format!(
+            "{} repositories could not be restored, {} only partially",
+            failed, partial
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -1,8 +1,9 @@
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct GitHubConfig {
     /// GitHub token for API access
     pub token: Option<String>,
@@ -10,17 +11,58 @@ pub struct GitHubConfig {
     /// List of static branches to always keep
     #[serde(default)]
     pub protected_branches: Vec<String>,
+
+    /// Most open pull requests listed per repository, the rest are only counted
+    pub max_prs: Option<usize>,
+
+    /// API used to look up pull requests
+    #[serde(default)]
+    pub backend: GithubBackend,
+}
+
+/// How pull requests are looked up on GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum GithubBackend {
+    /// One REST request per repository
+    #[default]
+    Rest,
+    /// Aliased GraphQL queries covering many repositories each, needs a token
+    Graphql,
+}
+
+/// Pull requests listed per repository when `max_prs` isn't set, one API page
+pub const DEFAULT_MAX_PRS: usize = 100;
+
+/// Names of the remotes that play a role in the checks
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RemoteNames {
+    /// Remote we push to, ahead checks and PRs are looked up against it
+    #[serde(default = "default_primary_remote")]
+    pub primary: String,
+
+    /// Remote of the repository a fork was made from
+    #[serde(default = "default_upstream_remote")]
+    pub upstream: String,
 }
 
-impl Default for GitHubConfig {
+impl Default for RemoteNames {
     fn default() -> Self {
         Self {
-            token: None,
-            protected_branches: Vec::new(),
+            primary: default_primary_remote(),
+            upstream: default_upstream_remote(),
         }
     }
 }
 
+fn default_primary_remote() -> String {
+    "origin".to_string()
+}
+
+fn default_upstream_remote() -> String {
+    "upstream".to_string()
+}
+
 /// Config file structure that can be loaded from TOML
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -48,6 +90,29 @@ pub struct Config {
     #[serde(default = "default_true")]
     pub include_draft_prs: bool,
 
+    /// Only flag uncommitted work untouched for at least this long, e.g. "3d"
+    pub dirty_warn_after: Option<String>,
+
+    /// Output template for each repository, inline or `@path` to read it from a file
+    pub template: Option<String>,
+
+    /// Expect repositories at `<root>/<host>/<owner>/<repo>` and flag the ones elsewhere
+    #[serde(default = "default_false")]
+    pub canonical_layout: bool,
+
+    /// Check that every remote answers and that GitHub remotes weren't moved or archived
+    #[serde(default = "default_false")]
+    pub check_remote_health: bool,
+
+    /// Forget recorded scans older than this, e.g. "26w", defaults to a year
+    pub history_retention: Option<String>,
+
+    /// Workspace manifest listing the repositories expected in the scan directory
+    pub manifest: Option<PathBuf>,
+
+    #[serde(default)]
+    pub remotes: RemoteNames,
+
     #[serde(default)]
     pub github: GitHubConfig,
 }
@@ -75,6 +140,13 @@ impl Default for Config {
             check_branch: true,
             check_prs: false,
             include_draft_prs: true,
+            dirty_warn_after: None,
+            template: None,
+            canonical_layout: false,
+            check_remote_health: false,
+            history_retention: None,
+            manifest: None,
+            remotes: RemoteNames::default(),
             github: GitHubConfig::default(),
         }
     }
@@ -115,11 +187,66 @@ pub fn load_config(config_path: Option<&Path>) -> Result<Config, Box<dyn std::er
 
     // Read and parse config file
     let config_content = fs::read_to_string(&config_path)?;
-    let config: Config = toml::from_str(&config_content)?;
+    let mut config: Config = toml::from_str(&config_content)?;
+
+    // A relative manifest is next to the config, not wherever gitgud runs from
+    if let (Some(manifest), Some(config_dir)) = (&mut config.manifest, config_path.parent()) {
+        if manifest.is_relative() {
+            *manifest = config_dir.join(&*manifest);
+        }
+    }
 
     Ok(config)
 }
 
+/// Repositories a team expects in the workspace
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct WorkspaceManifest {
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<ManifestRepo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ManifestRepo {
+    /// URL the repository is cloned from, expected as its `origin`
+    pub url: String,
+
+    /// Target path relative to the scanned directory, defaults to the name in the URL
+    pub path: Option<PathBuf>,
+}
+
+impl ManifestRepo {
+    /// Target path relative to the scanned directory, refusing paths that leave it
+    pub fn target_path(&self) -> Result<PathBuf, String> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => {
+                let name = self.url.trim_end_matches('/').trim_end_matches(".git");
+                let name = name.rsplit(['/', ':']).next().unwrap_or(name);
+                PathBuf::from(name)
+            }
+        };
+
+        let inside = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside || path.components().all(|c| c == Component::CurDir) {
+            return Err(format!(
+                "path '{}' for {} is not inside the workspace",
+                path.display(),
+                self.url
+            ));
+        }
+        Ok(path)
+    }
+}
+
+pub fn load_manifest(path: &Path) -> Result<WorkspaceManifest, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read manifest {}: {}", path.display(), e))?;
+    Ok(toml::from_str(&content)?)
+}
+
 // Function to load protected branches from file
 pub fn load_protected_branches(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     let content = fs::read_to_string(path)?;
@@ -131,3 +258,92 @@ pub fn load_protected_branches(path: &Path) -> Result<Vec<String>, Box<dyn std::
 
     Ok(branches)
 }
+
+/// Parse a duration like `90s`, `15m`, `12h`, `3d` or `2w` into seconds
+pub fn parse_duration(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", value))?;
+    let multiplier = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        other => return Err(format!("unknown duration unit '{}' in '{}'", other, value)),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("duration '{}' is too long", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("0"), Ok(0));
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("45s"), Ok(45));
+        assert_eq!(parse_duration("2m"), Ok(120));
+        assert_eq!(parse_duration(" 12h "), Ok(12 * 60 * 60));
+        assert_eq!(parse_duration("3d"), Ok(3 * 24 * 60 * 60));
+        assert_eq!(parse_duration("26w"), Ok(26 * 7 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("1.5h").is_err());
+    }
+
+    fn manifest_repo(url: &str, path: Option<&str>) -> ManifestRepo {
+        ManifestRepo {
+            url: url.to_string(),
+            path: path.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn manifest_paths_stay_inside_the_workspace() {
+        let url = "git@github.com:owner/repo.git";
+        assert_eq!(
+            manifest_repo(url, None).target_path(),
+            Ok(PathBuf::from("repo"))
+        );
+        assert_eq!(
+            manifest_repo(url, Some("team/repo")).target_path(),
+            Ok(PathBuf::from("team/repo"))
+        );
+
+        for path in ["/etc/repo", "../repo", "team/../../repo", "", "."] {
+            assert!(
+                manifest_repo(url, Some(path)).target_path().is_err(),
+                "{}",
+                path
+            );
+        }
+        assert!(manifest_repo("https://example.com/..", None)
+            .target_path()
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert!(parse_duration("18446744073709551615").is_ok());
+        assert!(parse_duration("18446744073709551615m").is_err());
+        assert!(parse_duration("99999999999999999w").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
+}
@@ -1,7 +1,322 @@
+use crate::args::ColorChoice;
+use crate::fix::shell_quote;
+use crate::github::pr::PullRequestInfo;
+use crate::history::{now_timestamp, CheckHistory, WeekTrend};
+use crate::repo::diff::RepoChanges;
 use crate::repo::filters::RepoFilters;
+use crate::repo::findings::{CheckKind, Finding};
 use crate::repo::status::RepoStatus;
+use crate::template::{Template, TemplateContext, Value};
+use chrono::{Local, TimeZone};
 use colored::*;
+use std::io::IsTerminal;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static HYPERLINKS: AtomicBool = AtomicBool::new(false);
+
+/// Every field the template contexts below provide, user templates may only use these
+pub const TEMPLATE_FIELDS: &[&str] = &[
+    // Repositories
+    "path",
+    "name",
+    "branch",
+    "non_default_branch",
+    "untracked",
+    "unstaged",
+    "ahead",
+    "ahead_commits",
+    "missing_remote",
+    "canonical_path",
+    "rate_limited",
+    "fetch_error",
+    "remote_issues",
+    "behind_upstream",
+    "merged_upstream",
+    "upstream_repo",
+    "upstream_pr_count",
+    "upstream_prs",
+    "dirty_age",
+    "oldest_change_age",
+    "stale_for",
+    "pr_count",
+    "unlisted_pr_count",
+    "has_issues",
+    "prs",
+    "findings",
+    // Pull requests
+    "number",
+    "url",
+    "pr_link",
+    "title",
+    "draft",
+    "draft_marker",
+    "review_decision",
+    "ci_status",
+    "status_marker",
+    // Findings
+    "check",
+    "detail",
+];
+
+/// One of the default output lines, parsed the first time it is printed
+struct BuiltinLine {
+    source: &'static str,
+    template: OnceLock<Template>,
+}
+
+impl BuiltinLine {
+    const fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            template: OnceLock::new(),
+        }
+    }
+
+    fn render(&self, context: &dyn TemplateContext) -> String {
+        self.template
+            .get_or_init(|| Template::parse(self.source).expect("built-in template is valid"))
+            .render(context)
+    }
+}
+
+static UNTRACKED_LINE: BuiltinLine = BuiltinLine::new(
+    "[+] {path} => untracked files found{#stale_for} (untouched for {stale_for}){/stale_for}",
+);
+static UNSTAGED_LINE: BuiltinLine = BuiltinLine::new(
+    "[~] {path} => changes not staged for commit{#stale_for} (untouched for {stale_for}){/stale_for}",
+);
+static AHEAD_LINE: BuiltinLine = BuiltinLine::new("[!] {path} => branch ahead of remote");
+static MISSING_REMOTE_LINE: BuiltinLine = BuiltinLine::new("[!] {path} => repo missing remote");
+static FETCH_FAILED_LINE: BuiltinLine =
+    BuiltinLine::new("[!] {path} => fetch failed: {fetch_error}");
+static REMOTE_HEALTH_LINE: BuiltinLine = BuiltinLine::new("[!] {path} => {remote_issues}");
+static RATE_LIMITED_LINE: BuiltinLine =
+    BuiltinLine::new("[!] {path} => rate limited, GitHub checks skipped");
+static MISPLACED_LINE: BuiltinLine =
+    BuiltinLine::new("[>] {path} => not in canonical location, expected {canonical_path}");
+static BRANCH_LINE: BuiltinLine =
+    BuiltinLine::new("[!] {path} => currently on a checked out branch: {non_default_branch}");
+static BEHIND_UPSTREAM_LINE: BuiltinLine = BuiltinLine::new(
+    "[<] {path} => default branch {behind_upstream} commits behind {upstream_repo}",
+);
+static MERGED_UPSTREAM_LINE: BuiltinLine =
+    BuiltinLine::new("[~] {path} => branches already merged upstream: {merged_upstream}");
+static UPSTREAM_PR_HEADER_LINE: BuiltinLine =
+    BuiltinLine::new("[PR] {path} => {upstream_pr_count} open pull requests to {upstream_repo}:");
+static PR_HEADER_LINE: BuiltinLine =
+    BuiltinLine::new("[PR] {path} => {pr_count} open pull requests:");
+static MORE_PRS_LINE: BuiltinLine = BuiltinLine::new("     ... and {unlisted_pr_count} more");
+static PR_LINE: BuiltinLine = BuiltinLine::new(
+    "     {pr_link} {draft_marker|blue}{title|blue} - Branch: {branch|magenta}{status_marker|dim}",
+);
+
+/// Decide whether colors and hyperlinks are emitted for the rest of the run
+pub fn configure_output(color: ColorChoice, hyperlinks: bool) {
+    let enabled = match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            !no_color && std::io::stdout().is_terminal()
+        }
+    };
+
+    colored::control::set_override(enabled);
+    HYPERLINKS.store(enabled && hyperlinks, Ordering::Relaxed);
+}
+
+/// Wrap `text` in an OSC 8 hyperlink to `url` if hyperlinks are enabled
+pub fn hyperlink(url: &str, text: &str) -> String {
+    if hyperlinks_enabled() && !url.is_empty() {
+        format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn hyperlinks_enabled() -> bool {
+    HYPERLINKS.load(Ordering::Relaxed)
+}
+
+/// A path printed as is, linking to itself when hyperlinks are enabled
+pub fn path_link(path: &Path) -> String {
+    let text = path.display().to_string();
+    if hyperlinks_enabled() {
+        hyperlink(&file_url(path), &text)
+    } else {
+        text
+    }
+}
+
+/// Percent-encode a path for use in a URL, keeping `/` and unreserved characters
+pub fn percent_encode_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// `file://` URL for a local path, absolutized so terminals can open it
+pub fn file_url(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    format!("file://{}", percent_encode_path(&path))
+}
+
+/// Template fields for a single repository
+pub struct RepoContext<'a> {
+    pub path: &'a Path,
+    pub status: &'a RepoStatus,
+    pub filters: &'a RepoFilters,
+}
+
+impl RepoContext<'_> {
+    fn dirty_age(&self) -> String {
+        self.status
+            .dirty_age(chrono::Utc::now().timestamp())
+            .map(format_duration)
+            .unwrap_or_default()
+    }
+}
+
+impl TemplateContext for RepoContext<'_> {
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        let status = self.status;
+        let value = match name {
+            // Resolving the URL touches the filesystem, only do it when it is used
+            "path" => Value::Link {
+                text: self.path.display().to_string(),
+                url: if hyperlinks_enabled() {
+                    file_url(self.path)
+                } else {
+                    String::new()
+                },
+            },
+            "name" => Value::Text(
+                self.path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+            "branch" => Value::Text(status.current_branch.clone()),
+            "non_default_branch" => {
+                Value::Text(status.non_default_branch.clone().unwrap_or_default())
+            }
+            "untracked" => Value::Flag(status.untracked_files),
+            "unstaged" => Value::Flag(status.unstaged_changes),
+            "ahead" => Value::Flag(status.ahead_of_remote),
+            "ahead_commits" => Value::Number(status.ahead_commits.into()),
+            "missing_remote" => Value::Flag(status.missing_remote),
+            "canonical_path" => Value::Text(
+                status
+                    .canonical_path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            "rate_limited" => Value::Flag(status.rate_limited),
+            "fetch_error" => Value::Text(status.fetch_error.clone().unwrap_or_default()),
+            "remote_issues" => Value::Text(
+                status
+                    .remote_issues
+                    .iter()
+                    .map(|issue| issue.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+            "behind_upstream" => Value::Number(status.behind_upstream.unwrap_or(0).into()),
+            "merged_upstream" => Value::Text(status.merged_upstream.join(", ")),
+            "upstream_repo" => Value::Text(
+                status
+                    .upstream_repo
+                    .clone()
+                    .unwrap_or_else(|| self.filters.remotes.upstream.clone()),
+            ),
+            "upstream_pr_count" => Value::Number(status.upstream_prs.len() as u64),
+            "upstream_prs" => Value::List(
+                status
+                    .upstream_prs
+                    .iter()
+                    .map(|pr| Box::new(pr) as Box<dyn TemplateContext>)
+                    .collect(),
+            ),
+            "dirty_age" => Value::Text(self.dirty_age()),
+            "oldest_change_age" => Value::Text(
+                status
+                    .dirty_oldest_mtime
+                    .map(|mtime| format_duration(chrono::Utc::now().timestamp() - mtime))
+                    .unwrap_or_default(),
+            ),
+            // Only shown by the default lines when stale dirt is what gets flagged
+            "stale_for" if self.filters.dirty_warn_after.is_some() => Value::Text(self.dirty_age()),
+            "pr_count" => Value::Number(status.open_pr_count as u64),
+            "unlisted_pr_count" => {
+                Value::Number(status.open_pr_count.saturating_sub(status.open_prs.len()) as u64)
+            }
+            "has_issues" => Value::Flag(status.has_issues(self.filters)),
+            "prs" => Value::List(
+                status
+                    .open_prs
+                    .iter()
+                    .map(|pr| Box::new(pr) as Box<dyn TemplateContext>)
+                    .collect(),
+            ),
+            "findings" => Value::List(
+                status
+                    .findings(self.filters)
+                    .into_iter()
+                    .map(|finding| Box::new(finding) as Box<dyn TemplateContext>)
+                    .collect(),
+            ),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl TemplateContext for &PullRequestInfo {
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        let value = match name {
+            "number" => Value::Number(self.number),
+            "url" => Value::Text(self.url.clone()),
+            "pr_link" => Value::Link {
+                text: format!("#{}", self.number),
+                url: self.url.clone(),
+            },
+            "title" => Value::Text(self.title.clone()),
+            "branch" => Value::Text(self.branch.clone()),
+            "draft" => Value::Flag(self.is_draft),
+            "draft_marker" => Value::Text(if self.is_draft { "[DRAFT] " } else { "" }.to_string()),
+            "review_decision" => Value::Text(self.review_decision.clone().unwrap_or_default()),
+            "ci_status" => Value::Text(self.ci_status.clone().unwrap_or_default()),
+            "status_marker" => Value::Text(
+                self.status_summary()
+                    .map(|summary| format!(" [{}]", summary))
+                    .unwrap_or_default(),
+            ),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl TemplateContext for Finding {
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        match name {
+            "check" => Some(Value::Text(self.check.id().to_string())),
+            "detail" => Some(Value::Text(self.detail.clone())),
+            _ => None,
+        }
+    }
+}
 
 pub fn display_repos_status(results: &[(std::path::PathBuf, RepoStatus)], filters: &RepoFilters) {
     for (repo_path, status) in results {
@@ -9,95 +324,392 @@ pub fn display_repos_status(results: &[(std::path::PathBuf, RepoStatus)], filter
     }
 }
 
+/// Print every repository through a user supplied template
+pub fn display_repos_template(
+    results: &[(std::path::PathBuf, RepoStatus)],
+    filters: &RepoFilters,
+    template: &Template,
+) {
+    for (repo_path, status) in results {
+        let context = RepoContext {
+            path: repo_path,
+            status,
+            filters,
+        };
+        let rendered = template.render(&context);
+
+        if rendered.is_empty() {
+            continue;
+        }
+
+        if rendered.ends_with('\n') {
+            print!("{}", rendered);
+        } else {
+            println!("{}", rendered);
+        }
+    }
+}
+
+/// Print a failed check, dimmed with a marker when it has been acknowledged
+fn print_check_line(status: &RepoStatus, check: CheckKind, line: String, color: Color) {
+    match status.acknowledged.get(&check) {
+        Some(ack) => println!(
+            "{}",
+            format!(
+                "{} [acknowledged until {}: {}]",
+                line, ack.until, ack.reason
+            )
+            .dimmed()
+        ),
+        None => println!("{}", line.color(color).bold()),
+    }
+}
+
 pub fn display_repo_status(repo_path: &Path, status: &RepoStatus, filters: &RepoFilters) {
+    let context = RepoContext {
+        path: repo_path,
+        status,
+        filters,
+    };
     let mut printed = false;
 
-    if filters.check_untracked && status.untracked_files {
-        println!(
-            "{}",
-            format!("[+] {} => untracked files found", repo_path.display())
-                .green()
-                .bold()
+    if filters.check_untracked
+        && status.untracked_files
+        && !status.is_fresh_dirt(CheckKind::Untracked, filters)
+    {
+        print_check_line(
+            status,
+            CheckKind::Untracked,
+            UNTRACKED_LINE.render(&context),
+            Color::Green,
         );
         printed = true;
     }
 
-    if filters.check_unstaged && status.unstaged_changes {
-        println!(
-            "{}",
-            format!(
-                "[~] {} => changes not staged for commit",
-                repo_path.display()
-            )
-            .yellow()
-            .bold()
+    if filters.check_unstaged
+        && status.unstaged_changes
+        && !status.is_fresh_dirt(CheckKind::Unstaged, filters)
+    {
+        print_check_line(
+            status,
+            CheckKind::Unstaged,
+            UNSTAGED_LINE.render(&context),
+            Color::Yellow,
         );
         printed = true;
     }
 
     if filters.check_ahead && status.ahead_of_remote {
-        println!(
-            "{}",
-            format!("[!] {} => branch ahead of remote", repo_path.display())
-                .red()
-                .bold()
+        print_check_line(
+            status,
+            CheckKind::Ahead,
+            AHEAD_LINE.render(&context),
+            Color::Red,
         );
         printed = true;
     }
 
     if filters.check_no_remotes && status.missing_remote {
-        println!(
-            "{}",
-            format!("[!] {} => repo missing remote", repo_path.display())
-                .red()
-                .bold()
+        print_check_line(
+            status,
+            CheckKind::NoRemote,
+            MISSING_REMOTE_LINE.render(&context),
+            Color::Red,
         );
         printed = true;
     }
 
-    if filters.check_branch {
-        if let Some(branch) = &status.non_default_branch {
-            println!(
-                "{}",
-                format!(
-                    "[!] {} => currently on a checked out branch: {}",
-                    repo_path.display(),
-                    branch
-                )
-                .cyan()
-                .bold()
-            );
-            printed = true;
+    if filters.check_fetch && status.fetch_error.is_some() {
+        print_check_line(
+            status,
+            CheckKind::FetchFailed,
+            FETCH_FAILED_LINE.render(&context),
+            Color::Red,
+        );
+        printed = true;
+    }
+
+    if filters.check_remote_health && !status.remote_issues.is_empty() {
+        print_check_line(
+            status,
+            CheckKind::RemoteHealth,
+            REMOTE_HEALTH_LINE.render(&context),
+            Color::Red,
+        );
+
+        // Moved repositories only need their remote pointed at the new URL
+        for issue in &status.remote_issues {
+            if let Some(url) = issue.new_url() {
+                let command = format!(
+                    "git -C {} remote set-url {} {}",
+                    shell_quote(&repo_path.display().to_string()),
+                    shell_quote(&issue.remote),
+                    shell_quote(url)
+                );
+                println!("     {}", command.dimmed());
+            }
         }
+
+        printed = true;
+    }
+
+    if filters.check_branch && status.non_default_branch.is_some() {
+        print_check_line(
+            status,
+            CheckKind::Branch,
+            BRANCH_LINE.render(&context),
+            Color::Cyan,
+        );
+        printed = true;
+    }
+
+    if filters.check_layout && status.canonical_path.is_some() {
+        print_check_line(
+            status,
+            CheckKind::Misplaced,
+            MISPLACED_LINE.render(&context),
+            Color::Magenta,
+        );
+        printed = true;
+    }
+
+    if (filters.check_prs || filters.check_fork) && status.rate_limited {
+        print_check_line(
+            status,
+            CheckKind::RateLimited,
+            RATE_LIMITED_LINE.render(&context),
+            Color::Yellow,
+        );
+        printed = true;
     }
 
     // Display PR information
-    if filters.check_prs && !status.open_prs.is_empty() {
+    if filters.check_prs && status.open_pr_count > 0 {
+        print_check_line(
+            status,
+            CheckKind::OpenPrs,
+            PR_HEADER_LINE.render(&context),
+            Color::Blue,
+        );
+
+        for pr in &status.open_prs {
+            println!("{}", PR_LINE.render(&pr));
+        }
+        if status.open_pr_count > status.open_prs.len() {
+            println!("{}", MORE_PRS_LINE.render(&context));
+        }
+
+        printed = true;
+    }
+
+    if filters.check_fork && status.finding(CheckKind::BehindUpstream).is_some() {
+        print_check_line(
+            status,
+            CheckKind::BehindUpstream,
+            BEHIND_UPSTREAM_LINE.render(&context),
+            Color::Magenta,
+        );
+        printed = true;
+    }
+
+    if filters.check_fork && !status.merged_upstream.is_empty() {
+        print_check_line(
+            status,
+            CheckKind::MergedUpstream,
+            MERGED_UPSTREAM_LINE.render(&context),
+            Color::Magenta,
+        );
+        printed = true;
+    }
+
+    if filters.check_fork && !status.upstream_prs.is_empty() {
+        print_check_line(
+            status,
+            CheckKind::UpstreamPrs,
+            UPSTREAM_PR_HEADER_LINE.render(&context),
+            Color::Blue,
+        );
+
+        for pr in &status.upstream_prs {
+            println!("{}", PR_LINE.render(&pr));
+        }
+
+        printed = true;
+    }
+
+    if printed {
+        println!();
+    }
+}
+
+/// Short human readable duration like `3d 4h` or `25m`
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+pub fn display_repo_history(repo: &str, history: &[CheckHistory]) {
+    println!("{}", format!("[?] History for {}", repo).cyan().bold());
+
+    let now = now_timestamp();
+    for check in history {
+        let state = if check.failing { "failing" } else { "passing" };
+        let line = format!(
+            "{:<10} {} for {} (since {}, last scanned {}, {} changes)",
+            check.check_id,
+            state,
+            format_duration(now - check.since),
+            format_timestamp(check.since),
+            format_timestamp(check.last_seen),
+            check.transitions
+        );
+
+        if check.failing {
+            let detail = check.detail.as_deref().unwrap_or_default();
+            println!("{} {}", format!("[!] {}", line).red().bold(), detail);
+        } else {
+            println!("{}", format!("[+] {}", line).green());
+        }
+    }
+}
+
+pub fn display_trend(trend: &[WeekTrend]) {
+    let checks: Vec<&str> = CheckKind::ALL.iter().map(|check| check.id()).collect();
+
+    let mut header = format!("{:<10} {:>6}", "week", "total");
+    for check in &checks {
+        header.push_str(&format!(" {:>10}", check));
+    }
+    println!("{}", header.bold());
+
+    for week in trend {
+        let mut line = format!("{:<10} {:>6}", week.week, week.total());
+        for check in &checks {
+            let count = week
+                .counts
+                .iter()
+                .find(|(id, _)| id == check)
+                .map(|(_, count)| *count)
+                .unwrap_or_default();
+            line.push_str(&format!(" {:>10}", count));
+        }
+        println!("{}", line);
+    }
+}
+
+pub fn display_changes(changes: &[(std::path::PathBuf, RepoChanges)]) {
+    if changes.is_empty() {
         println!(
             "{}",
-            format!(
-                "[PR] {} => {} open pull requests:",
-                repo_path.display(),
-                status.open_prs.len()
-            )
-            .blue()
-            .bold()
+            "[=] Nothing changed since the last scan".green().bold()
         );
+        println!();
+        return;
+    }
 
-        for pr in &status.open_prs {
-            let draft_marker = if pr.is_draft { "[DRAFT] " } else { "" };
+    for (repo_path, repo_changes) in changes {
+        let path = path_link(repo_path);
+
+        for finding in &repo_changes.new_findings {
             println!(
-                "     #{} {} - Branch: {}",
-                pr.number,
-                format!("{}{}", draft_marker, pr.title).blue(),
-                pr.branch.magenta()
+                "{}",
+                format!("[!] {} => new: {}", path, finding.detail)
+                    .red()
+                    .bold()
             );
         }
 
-        printed = true;
+        for check in &repo_changes.resolved {
+            println!(
+                "{}",
+                format!("[-] {} => resolved: {}", path, check.description()).green()
+            );
+        }
+
+        if repo_changes.now_clean {
+            println!("{}", format!("[=] {} => now clean", path).green().bold());
+        }
+
+        for pr in &repo_changes.new_prs {
+            println!(
+                "{}",
+                format!("[PR] {} => new pull request:", path).blue().bold()
+            );
+            println!("{}", PR_LINE.render(&pr));
+        }
+
+        println!();
     }
+}
 
-    if printed {
-        println!("");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_lines_only_use_known_fields() {
+        let lines = [
+            &UNTRACKED_LINE,
+            &UNSTAGED_LINE,
+            &AHEAD_LINE,
+            &MISSING_REMOTE_LINE,
+            &FETCH_FAILED_LINE,
+            &REMOTE_HEALTH_LINE,
+            &RATE_LIMITED_LINE,
+            &MISPLACED_LINE,
+            &BRANCH_LINE,
+            &BEHIND_UPSTREAM_LINE,
+            &MERGED_UPSTREAM_LINE,
+            &UPSTREAM_PR_HEADER_LINE,
+            &PR_HEADER_LINE,
+            &MORE_PRS_LINE,
+            &PR_LINE,
+        ];
+
+        for line in lines {
+            let template = Template::parse(line.source).unwrap();
+            assert!(
+                template.validate(TEMPLATE_FIELDS).is_ok(),
+                "{}",
+                line.source
+            );
+        }
+    }
+
+    #[test]
+    fn percent_encodes_paths() {
+        assert_eq!(
+            percent_encode_path(Path::new("/home/me/a b/c#d%e?f")),
+            "/home/me/a%20b/c%23d%25e%3Ff"
+        );
+        assert_eq!(
+            percent_encode_path(Path::new("/tmp/ünï")),
+            "/tmp/%C3%BCn%C3%AF"
+        );
+        assert_eq!(percent_encode_path(Path::new("a-b_c.d~e")), "a-b_c.d~e");
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(-5), "0m");
+        assert_eq!(format_duration(25 * 60), "25m");
+        assert_eq!(format_duration(3 * 3600 + 120), "3h 2m");
+        assert_eq!(format_duration(3 * 86400 + 4 * 3600), "3d 4h");
     }
 }
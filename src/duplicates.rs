@@ -0,0 +1,176 @@
+use crate::config::RemoteNames;
+use crate::display::path_link;
+use crate::git::branches::unpushed_branches;
+use crate::git::remote::list_remotes;
+use crate::git::stash::list_stashes;
+use crate::git::status::check_git_status;
+use crate::git::url::normalize_remote_url;
+use crate::repo::filters::RepoFilters;
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Work in a clone that would be lost if it were deleted
+fn local_work(repo_path: &Path) -> Vec<String> {
+    // Only the worktree state is read, skip the ahead, remote and branch lookups
+    let filters = RepoFilters {
+        check_untracked: true,
+        check_unstaged: true,
+        check_ahead: false,
+        check_no_remotes: false,
+        check_branch: false,
+        check_prs: false,
+        include_draft_prs: false,
+        dirty_warn_after: None,
+        check_fetch: false,
+        check_layout: false,
+        check_fork: false,
+        check_remote_health: false,
+        remotes: RemoteNames::default(),
+    };
+    let status = check_git_status(repo_path, &filters, false);
+    let mut work = Vec::new();
+
+    let unpushed: Vec<String> = unpushed_branches(repo_path)
+        .into_iter()
+        .map(|(branch, count)| format!("{} ({})", branch, count))
+        .collect();
+    if !unpushed.is_empty() {
+        work.push(format!("unpushed commits on {}", unpushed.join(", ")));
+    }
+
+    let stashes = list_stashes(repo_path).len();
+    if stashes > 0 {
+        work.push(format!("{} stashes", stashes));
+    }
+
+    if status.unstaged_changes {
+        work.push("changes not staged for commit".to_string());
+    }
+    if status.untracked_files {
+        work.push("untracked files".to_string());
+    }
+    work
+}
+
+/// Group repositories by each of their normalized remotes, keeping only remotes cloned more than once
+///
+/// The upstream remote is left out, forks of the same project are not copies of each other.
+pub fn find_duplicates(repos: &[PathBuf], remotes: &RemoteNames) -> BTreeMap<String, Vec<PathBuf>> {
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for repo_path in repos {
+        let urls: BTreeSet<String> = list_remotes(repo_path)
+            .iter()
+            .filter(|remote| remote.name != remotes.upstream)
+            .map(|remote| normalize_remote_url(&remote.url))
+            .collect();
+
+        for url in urls {
+            groups.entry(url).or_default().push(repo_path.clone());
+        }
+    }
+
+    groups.retain(|_, clones| clones.len() > 1);
+    groups
+}
+
+/// Print every remote that is cloned more than once and what each copy holds
+pub fn run_duplicates(repos: &[PathBuf], remotes: &RemoteNames) {
+    let duplicates = find_duplicates(repos, remotes);
+
+    if duplicates.is_empty() {
+        println!(
+            "{}",
+            "[=] No remote is cloned more than once".green().bold()
+        );
+        return;
+    }
+
+    for (remote, clones) in &duplicates {
+        println!(
+            "{}",
+            format!("[dup] {} is cloned {} times:", remote, clones.len())
+                .yellow()
+                .bold()
+        );
+
+        for repo_path in clones {
+            let path = path_link(repo_path);
+            let work = local_work(repo_path);
+
+            if work.is_empty() {
+                println!("      {} {}", path, "- no local work".green());
+            } else {
+                println!("      {} {}", path, format!("- {}", work.join(", ")).red());
+            }
+        }
+        println!();
+    }
+
+    println!(
+        "Duplicates: {} remotes cloned more than once",
+        duplicates.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    fn repo_with_remotes(dir: &Path, remotes: &[(&str, &str)]) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        for (name, url) in remotes {
+            git(&["remote", "add", name, url]);
+        }
+        dir.to_path_buf()
+    }
+
+    #[test]
+    fn groups_clones_by_any_remote() {
+        let root = std::env::temp_dir().join(format!("gitgud-duplicates-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+
+        let https = repo_with_remotes(
+            &root.join("https"),
+            &[("origin", "https://github.com/owner/repo.git")],
+        );
+        let mirror = repo_with_remotes(
+            &root.join("mirror"),
+            &[
+                ("origin", "https://gitlab.com/me/mirror.git"),
+                ("github", "git@github.com:Owner/repo.git"),
+            ],
+        );
+        let fork = repo_with_remotes(
+            &root.join("fork"),
+            &[
+                ("origin", "git@github.com:me/repo.git"),
+                ("upstream", "https://github.com/owner/repo"),
+            ],
+        );
+
+        let duplicates = find_duplicates(
+            &[https.clone(), mirror.clone(), fork],
+            &RemoteNames::default(),
+        );
+        assert_eq!(
+            duplicates,
+            BTreeMap::from([("github.com/owner/repo".to_string(), vec![https, mirror])])
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,525 @@
+use crate::display::path_link;
+use crate::git::branches::{
+    default_branch, is_worktree_clean, list_local_branches, merged_branches, unpushed_commits,
+    BranchInfo,
+};
+use crate::git::remote::primary_remote;
+use crate::repo::findings::CheckKind;
+use crate::repo::scan::ScanOptions;
+use colored::Colorize;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How `gitgud fix` treats the fixes it finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixMode {
+    /// Only print the commands
+    DryRun,
+    /// Run every command without asking
+    Apply,
+    /// Ask once per repository before running its commands
+    Interactive,
+}
+
+/// A single remediation, run as `git -C <repo> <args>`
+#[derive(Debug, Clone)]
+pub struct FixAction {
+    /// Check this fix resolves, acknowledged checks are left alone
+    pub check: Option<CheckKind>,
+    pub description: String,
+    pub args: Vec<String>,
+}
+
+impl FixAction {
+    fn new(check: Option<CheckKind>, description: String, args: &[&str]) -> Self {
+        Self {
+            check,
+            description,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// The exact command line, quoted so it can be pasted into a shell
+    pub fn command_line(&self, repo_path: &Path) -> String {
+        let mut words = vec![
+            "git".to_string(),
+            "-C".to_string(),
+            shell_quote(&repo_path.display().to_string()),
+        ];
+        words.extend(self.args.iter().map(|arg| shell_quote(arg)));
+        words.join(" ")
+    }
+
+    fn run(&self, repo_path: &Path) -> Result<(), String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_path)
+            .args(&self.args)
+            // Never hang on a credential prompt
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .map_err(|e| format!("Unable to run git: {}", e))?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+/// Quote `word` for a POSIX shell when it contains anything unusual
+pub fn shell_quote(word: &str) -> String {
+    let safe = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+
+    if safe {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+fn commits(count: u32) -> String {
+    if count == 1 {
+        "1 commit".to_string()
+    } else {
+        format!("{} commits", count)
+    }
+}
+
+/// Work out the safe fixes for one repository, in the order they should run
+///
+/// Nothing here can lose work: pushes and fast-forwards never rewrite history,
+/// branches are only deleted with `git branch -d` once merged into the default
+/// branch, and switching branches requires a clean worktree.
+pub fn plan_fixes(repo_path: &Path, options: &ScanOptions) -> Vec<FixAction> {
+    let filters = &options.filters;
+    let branches = list_local_branches(repo_path);
+    let default = default_branch(&branches);
+    let clean = is_worktree_clean(repo_path);
+    let current = branches.iter().find(|branch| branch.is_current);
+    let mut actions = Vec::new();
+
+    if filters.check_ahead {
+        // New branches get published on the primary remote
+        let new_branch_remote =
+            primary_remote(repo_path, &filters.remotes).map(|remote| remote.name);
+
+        for branch in &branches {
+            match (&branch.remote, &branch.remote_ref) {
+                (Some(remote), Some(remote_ref)) if branch.ahead > 0 && !branch.gone => {
+                    if branch.behind > 0 {
+                        // Diverged, that needs a human
+                        continue;
+                    }
+                    actions.push(FixAction::new(
+                        Some(CheckKind::Ahead),
+                        format!(
+                            "push {} on {} to {}",
+                            commits(branch.ahead),
+                            branch.name,
+                            remote
+                        ),
+                        &["push", remote, &format!("{}:{}", branch.name, remote_ref)],
+                    ));
+                }
+                (None, _) if branch.upstream.is_none() => {
+                    let Some(remote) = &new_branch_remote else {
+                        continue;
+                    };
+                    // Branches that only point at pushed history have nothing to lose
+                    let unpushed = unpushed_commits(repo_path, &branch.name);
+                    if unpushed == 0 {
+                        continue;
+                    }
+                    actions.push(FixAction::new(
+                        Some(CheckKind::Ahead),
+                        format!(
+                            "publish {} with {} and track it on {}",
+                            branch.name,
+                            commits(unpushed),
+                            remote
+                        ),
+                        &["push", "--set-upstream", remote, &branch.name],
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for branch in branches
+        .iter()
+        .filter(|branch| branch.behind > 0 && branch.ahead == 0 && !branch.gone)
+    {
+        let Some(upstream) = &branch.upstream else {
+            continue;
+        };
+        let description = format!("fast-forward {} by {}", branch.name, commits(branch.behind));
+
+        if branch.is_current {
+            if clean {
+                actions.push(FixAction::new(
+                    None,
+                    description,
+                    &["merge", "--ff-only", upstream],
+                ));
+            }
+        } else {
+            // Fetching from the repository itself only ever fast-forwards
+            actions.push(FixAction::new(
+                None,
+                description,
+                &[
+                    "fetch",
+                    ".",
+                    &format!("{}:refs/heads/{}", upstream, branch.name),
+                ],
+            ));
+        }
+    }
+
+    let Some(default) = default else {
+        return actions;
+    };
+
+    let switching = match current {
+        Some(current)
+            if filters.check_branch
+                && clean
+                && current.name != default
+                && !options.protected_branches.contains(&current.name) =>
+        {
+            actions.push(FixAction::new(
+                Some(CheckKind::Branch),
+                format!("switch from {} back to {}", current.name, default),
+                &["switch", &default],
+            ));
+            true
+        }
+        _ => false,
+    };
+
+    let merged = merged_branches(repo_path, &default);
+    let prunable = |branch: &&BranchInfo| {
+        branch.gone
+            && branch.name != default
+            && (!branch.is_current || switching)
+            && !options.protected_branches.contains(&branch.name)
+            && merged.contains(&branch.name)
+    };
+    for branch in branches.iter().filter(prunable) {
+        actions.push(FixAction::new(
+            None,
+            format!(
+                "delete {}, merged into {} and gone from its remote",
+                branch.name, default
+            ),
+            &["branch", "-d", &branch.name],
+        ));
+    }
+
+    actions
+}
+
+fn confirm(repo_path: &Path) -> Result<Option<bool>, Box<dyn std::error::Error>> {
+    print!("Apply these fixes to {}? [y/N/q] ", repo_path.display());
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "q" | "quit" => None,
+        _ => Some(false),
+    })
+}
+
+/// Plan fixes for every repository and print, apply or offer them depending on `mode`
+pub fn run_fix(
+    repos: &[PathBuf],
+    options: &ScanOptions,
+    mode: FixMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut planned = 0;
+    let mut failed = 0;
+
+    for repo_path in repos {
+        let acknowledged = options.acks.active_for(repo_path);
+        let actions: Vec<FixAction> = plan_fixes(repo_path, options)
+            .into_iter()
+            .filter(|action| !action.check.is_some_and(|c| acknowledged.contains_key(&c)))
+            .collect();
+
+        if actions.is_empty() {
+            if options.debug {
+                println!("[-] Nothing to fix in {}", repo_path.display());
+            }
+            continue;
+        }
+        planned += actions.len();
+
+        let path = path_link(repo_path);
+        println!("{}", format!("[fix] {}", path).cyan().bold());
+        for action in &actions {
+            println!("  {}", format!("# {}", action.description).dimmed());
+            println!("  {}", action.command_line(repo_path));
+        }
+
+        let apply = match mode {
+            FixMode::DryRun => false,
+            FixMode::Apply => true,
+            FixMode::Interactive => match confirm(repo_path)? {
+                Some(apply) => apply,
+                None => break,
+            },
+        };
+
+        if apply {
+            for action in &actions {
+                if let Err(e) = action.run(repo_path) {
+                    println!(
+                        "{}",
+                        format!("[!] {} failed: {}", action.command_line(repo_path), e)
+                            .red()
+                            .bold()
+                    );
+                    failed += 1;
+                    // Later fixes may depend on this one
+                    break;
+                }
+            }
+        }
+        println!();
+    }
+
+    if planned == 0 {
+        println!("{}", "[=] Nothing to fix".green().bold());
+    } else if mode == FixMode::DryRun {
+        println!(
+            "{}",
+            "[?] Dry run, re-run with --apply or --interactive to make these changes".cyan()
+        );
+    }
+
+    if failed > 0 {
+        return Err(format!("{} repositories could not be fixed completely", failed).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ack::AckStore;
+    use crate::github::client::GithubClient;
+    use crate::repo::filters::RepoFilters;
+    use std::fs;
+    use std::sync::Arc;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .env("GIT_AUTHOR_NAME", "gitgud")
+            .env("GIT_AUTHOR_EMAIL", "gitgud@example.com")
+            .env("GIT_COMMITTER_NAME", "gitgud")
+            .env("GIT_COMMITTER_EMAIL", "gitgud@example.com")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?}: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    fn commit(dir: &Path, message: &str) {
+        git(dir, &["commit", "--quiet", "--allow-empty", "-m", message]);
+    }
+
+    /// A bare `origin.git` with one commit on main and a clone of it in `work`
+    fn fixture(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("gitgud-fix-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        git(
+            &root,
+            &[
+                "init",
+                "--quiet",
+                "--bare",
+                "--initial-branch",
+                "main",
+                "origin.git",
+            ],
+        );
+        git(&root, &["clone", "--quiet", "origin.git", "work"]);
+        let work = root.join("work");
+        git(&work, &["switch", "--quiet", "--create", "main"]);
+        fs::write(work.join("README"), "hello\n").unwrap();
+        git(&work, &["add", "README"]);
+        commit(&work, "initial");
+        git(
+            &work,
+            &["push", "--quiet", "--set-upstream", "origin", "main"],
+        );
+        (root, work)
+    }
+
+    /// Push a commit to origin's main from another clone and fetch it into `work`
+    fn advance_origin(root: &Path, work: &Path) {
+        git(root, &["clone", "--quiet", "origin.git", "other"]);
+        let other = root.join("other");
+        commit(&other, "upstream change");
+        git(&other, &["push", "--quiet", "origin", "main"]);
+        git(work, &["fetch", "--quiet", "origin"]);
+    }
+
+    /// Local checks only, the GitHub client still needs a runtime to be built in
+    fn options(protected: &[&str]) -> ScanOptions {
+        ScanOptions {
+            filters: RepoFilters::git_only(Default::default()),
+            github: GithubClient::new(None).unwrap(),
+            max_prs: 0,
+            github_backend: Default::default(),
+            protected_branches: protected.iter().map(|name| name.to_string()).collect(),
+            debug: false,
+            cache: None,
+            acks: Arc::new(AckStore::default()),
+            layout_root: None,
+        }
+    }
+
+    fn planned(work: &Path, options: &ScanOptions) -> Vec<String> {
+        plan_fixes(work, options)
+            .iter()
+            .map(|action| action.args.join(" "))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn pushes_ahead_branches_and_publishes_new_ones() {
+        let (root, work) = fixture("push");
+        commit(&work, "unpushed");
+        git(&work, &["branch", "pointer", "origin/main"]);
+        git(&work, &["switch", "--quiet", "--create", "feature"]);
+        commit(&work, "feature work");
+        git(&work, &["switch", "--quiet", "main"]);
+
+        assert_eq!(
+            planned(&work, &options(&[])),
+            vec![
+                "push --set-upstream origin feature",
+                "push origin main:refs/heads/main",
+            ]
+        );
+
+        // Without the ahead check nothing gets pushed
+        let mut options = options(&[]);
+        options.filters.check_ahead = false;
+        assert!(planned(&work, &options).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn diverged_branches_are_left_alone() {
+        let (root, work) = fixture("diverged");
+        advance_origin(&root, &work);
+        commit(&work, "local change");
+
+        assert!(planned(&work, &options(&[])).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn behind_branches_fast_forward_only_over_a_clean_worktree() {
+        let (root, work) = fixture("behind");
+        git(&work, &["branch", "--track", "other", "origin/main"]);
+        advance_origin(&root, &work);
+
+        assert_eq!(
+            planned(&work, &options(&[])),
+            vec![
+                "merge --ff-only refs/remotes/origin/main",
+                "fetch . refs/remotes/origin/main:refs/heads/other",
+            ]
+        );
+
+        fs::write(work.join("README"), "edited\n").unwrap();
+        assert_eq!(
+            planned(&work, &options(&[])),
+            vec!["fetch . refs/remotes/origin/main:refs/heads/other"]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn switches_back_and_deletes_merged_branches_gone_from_the_remote() {
+        let (root, work) = fixture("prune");
+        for branch in ["done", "unmerged"] {
+            git(&work, &["switch", "--quiet", "--create", branch, "main"]);
+            if branch == "unmerged" {
+                commit(&work, "never merged");
+            }
+            git(
+                &work,
+                &["push", "--quiet", "--set-upstream", "origin", branch],
+            );
+            git(&work, &["push", "--quiet", "origin", "--delete", branch]);
+        }
+        git(&work, &["switch", "--quiet", "done"]);
+
+        assert_eq!(
+            planned(&work, &options(&[])),
+            vec!["switch main", "branch -d done"]
+        );
+
+        // Protected branches are neither left nor deleted
+        assert!(planned(&work, &options(&["done"])).is_empty());
+
+        // Switching needs a clean worktree, and the current branch can't be deleted
+        fs::write(work.join("README"), "edited\n").unwrap();
+        assert!(planned(&work, &options(&[])).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dry_run_changes_nothing() {
+        let (root, work) = fixture("dry-run");
+        commit(&work, "unpushed");
+
+        run_fix(std::slice::from_ref(&work), &options(&[]), FixMode::DryRun).unwrap();
+        assert_eq!(
+            planned(&work, &options(&[])),
+            vec!["push origin main:refs/heads/main"]
+        );
+
+        run_fix(std::slice::from_ref(&work), &options(&[]), FixMode::Apply).unwrap();
+        assert!(planned(&work, &options(&[])).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn command_lines_are_shell_quoted() {
+        let action = FixAction::new(None, String::new(), &["switch", "it's"]);
+        assert_eq!(
+            action.command_line(Path::new("/src/my repo")),
+            r"git -C '/src/my repo' switch 'it'\''s'"
+        );
+        assert_eq!(shell_quote("refs/heads/main:x@1"), "refs/heads/main:x@1");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,230 @@
+use crate::git::remote::list_remote_branches;
+use std::path::Path;
+use std::process::Command;
+
+/// A local branch and how it relates to its upstream
+#[derive(Debug, Clone)]
+pub struct BranchInfo {
+    pub name: String,
+    /// Full upstream ref, e.g. `refs/remotes/origin/main`
+    pub upstream: Option<String>,
+    /// Remote the upstream lives on
+    pub remote: Option<String>,
+    /// Branch ref on the remote, e.g. `refs/heads/main`
+    pub remote_ref: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// The upstream branch was deleted on the remote
+    pub gone: bool,
+    pub is_current: bool,
+}
+
+fn non_empty(field: &str) -> Option<String> {
+    if field.is_empty() {
+        None
+    } else {
+        Some(field.to_string())
+    }
+}
+
+/// Parse `[ahead N, behind M]` / `[gone]` as printed by `%(upstream:track)`
+fn parse_track(track: &str) -> (u32, u32, bool) {
+    let track = track.trim_start_matches('[').trim_end_matches(']');
+    if track == "gone" {
+        return (0, 0, true);
+    }
+
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ") {
+        if let Some(count) = part.strip_prefix("ahead ") {
+            ahead = count.parse().unwrap_or(0);
+        } else if let Some(count) = part.strip_prefix("behind ") {
+            behind = count.parse().unwrap_or(0);
+        }
+    }
+    (ahead, behind, false)
+}
+
+pub fn list_local_branches(repo_path: &Path) -> Vec<BranchInfo> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("for-each-ref")
+        .arg("--format=%(refname:short)%09%(upstream)%09%(upstream:remotename)%09%(upstream:remoteref)%09%(upstream:track)%09%(HEAD)")
+        .arg("refs/heads")
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                if fields.len() < 6 {
+                    return None;
+                }
+                let (ahead, behind, gone) = parse_track(fields[4]);
+                Some(BranchInfo {
+                    name: fields[0].to_string(),
+                    upstream: non_empty(fields[1]),
+                    remote: non_empty(fields[2]),
+                    remote_ref: non_empty(fields[3]),
+                    ahead,
+                    behind,
+                    gone,
+                    is_current: fields[5] == "*",
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The repository's default branch, `main` or `master` whichever exists locally
+pub fn default_branch(branches: &[BranchInfo]) -> Option<String> {
+    ["main", "master"]
+        .iter()
+        .find(|name| branches.iter().any(|branch| branch.name == **name))
+        .map(|name| name.to_string())
+}
+
+/// Local branches fully merged into `into`
+pub fn merged_branches(repo_path: &Path, into: &str) -> Vec<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("for-each-ref")
+        .arg("--format=%(refname:short)")
+        .arg(format!("--merged={}", into))
+        .arg("refs/heads")
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether the worktree has no staged, unstaged or untracked changes
+pub fn is_worktree_clean(repo_path: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("status")
+        .arg("--porcelain")
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .output()
+        .map(|output| output.status.success() && output.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+/// Commits on `branch` that are not on any remote-tracking branch
+pub fn unpushed_commits(repo_path: &Path, branch: &str) -> u32 {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-list")
+        .arg("--count")
+        .arg(format!("refs/heads/{}", branch))
+        .arg("--not")
+        .arg("--remotes")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Local branches with commits not on any remote, and how many
+pub fn unpushed_branches(repo_path: &Path) -> Vec<(String, u32)> {
+    list_local_branches(repo_path)
+        .into_iter()
+        .map(|branch| {
+            let count = unpushed_commits(repo_path, &branch.name);
+            (branch.name, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+/// Commits HEAD is ahead of and behind its upstream, `None` without an upstream
+pub fn upstream_divergence(repo_path: &Path) -> Option<(u32, u32)> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-list")
+        .arg("--left-right")
+        .arg("--count")
+        .arg("HEAD...@{upstream}")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let counts = String::from_utf8_lossy(&output.stdout);
+    let mut counts = counts.split_whitespace().map(|count| count.parse().ok());
+    Some((counts.next()??, counts.next()??))
+}
+
+/// Move the current branch to its upstream, refusing anything but a fast-forward
+pub fn fast_forward(repo_path: &Path) -> Result<(), String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("merge")
+        .arg("--ff-only")
+        .arg("--quiet")
+        .arg("@{upstream}")
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Default branch of `remote` as of the last fetch, from `<remote>/HEAD` or else main/master
+pub fn remote_default_branch(repo_path: &Path, remote: &str) -> Option<String> {
+    let prefix = format!("{}/", remote);
+    let head = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("symbolic-ref")
+        .arg("--quiet")
+        .arg("--short")
+        .arg(format!("refs/remotes/{}/HEAD", remote))
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+    if let Some(branch) = head.as_deref().and_then(|head| head.strip_prefix(&prefix)) {
+        return Some(branch.to_string());
+    }
+
+    let branches = list_remote_branches(repo_path, remote);
+    ["main", "master"]
+        .iter()
+        .find(|name| branches.iter().any(|branch| branch == *name))
+        .map(|name| name.to_string())
+}
+
+/// Commits in `to` that are not in `from`
+pub fn count_commits(repo_path: &Path, from: &str, to: &str) -> Option<u32> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-list")
+        .arg("--count")
+        .arg(format!("{}..{}", from, to))
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
@@ -0,0 +1,44 @@
+use crate::git::remote::list_remotes;
+use std::path::Path;
+use std::process::Command;
+
+/// Detailed per-repository information, too slow to gather for every scan
+#[derive(Debug, Clone, Default)]
+pub struct RepoDetails {
+    /// `git status --porcelain` lines
+    pub files: Vec<String>,
+    /// Local branches with their upstream tracking information
+    pub branches: Vec<String>,
+    /// Entries of `git stash list`
+    pub stashes: Vec<String>,
+    /// Every remote with the URL it fetches from
+    pub remotes: Vec<String>,
+}
+
+fn git_lines(repo_path: &Path, args: &[&str]) -> Vec<String> {
+    match Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(args)
+        .output()
+    {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .filter(|line| !line.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn load_repo_details(repo_path: &Path) -> RepoDetails {
+    RepoDetails {
+        files: git_lines(repo_path, &["status", "--porcelain"]),
+        branches: git_lines(repo_path, &["branch", "-vv", "--no-color"]),
+        stashes: git_lines(repo_path, &["stash", "list"]),
+        remotes: list_remotes(repo_path)
+            .into_iter()
+            .map(|remote| format!("{}  {}", remote.name, remote.url))
+            .collect(),
+    }
+}
@@ -1,2 +1,6 @@
+pub mod branches;
+pub mod details;
 pub mod remote;
+pub mod stash;
 pub mod status;
+pub mod url;
@@ -1,50 +1,161 @@
+use crate::config::RemoteNames;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::Duration;
 
-pub fn get_remote_url(repo_path: &Path) -> Option<String> {
+/// A configured remote and the URL it fetches from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remote {
+    pub name: String,
+    pub url: String,
+}
+
+/// Every remote of a repository, in the order git lists them
+pub fn list_remotes(repo_path: &Path) -> Vec<Remote> {
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_path)
-        .arg("config")
-        .arg("--get")
-        .arg("remote.origin.url")
-        .output()
-        .ok()?;
+        .arg("remote")
+        .arg("-v")
+        .output();
 
-    if output.status.success() {
-        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if !url.is_empty() {
-            return Some(url);
-        }
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.ends_with("(fetch)"))
+            .filter_map(|line| {
+                let (name, rest) = line.split_once('\t')?;
+                let url = rest.trim_end_matches("(fetch)").trim();
+                Some(Remote {
+                    name: name.to_string(),
+                    url: url.to_string(),
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
     }
+}
+
+/// The remote we push to and whose pull requests are checked
+///
+/// That is the configured primary remote when the repository has it, otherwise
+/// `origin`, otherwise the first remote that isn't the upstream of a fork.
+pub fn primary_remote(repo_path: &Path, names: &RemoteNames) -> Option<Remote> {
+    let remotes = list_remotes(repo_path);
 
-    None
+    let by_name = |name: &str| remotes.iter().find(|remote| remote.name == name).cloned();
+    by_name(&names.primary)
+        .or_else(|| by_name("origin"))
+        .or_else(|| {
+            remotes
+                .iter()
+                .find(|remote| remote.name != names.upstream)
+                .cloned()
+        })
+        .or_else(|| remotes.first().cloned())
 }
 
-pub fn list_remote_branches(repo_path: &Path) -> Vec<String> {
+/// Branches of `remote` as of the last fetch, without the `<remote>/` prefix
+pub fn list_remote_branches(repo_path: &Path, remote: &str) -> Vec<String> {
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_path)
         .arg("branch")
         .arg("-r")
+        .arg("--format=%(refname:short)")
         .output();
 
+    let prefix = format!("{}/", remote);
     match output {
-        Ok(output) if output.status.success() => {
-            String::from_utf8_lossy(&output.stdout)
-                .lines()
-                .map(|line| line.trim().to_string())
-                .filter(|line| !line.is_empty())
-                .map(|line| {
-                    // Strip "origin/" prefix if present
-                    if let Some(branch) = line.strip_prefix("origin/") {
-                        branch.to_string()
-                    } else {
-                        line
-                    }
-                })
-                .collect()
-        }
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix(&prefix))
+            // `<remote>/HEAD` is a pointer to the default branch, not a branch
+            .filter(|branch| !branch.is_empty() && *branch != "HEAD")
+            .map(str::to_string)
+            .collect(),
         _ => Vec::new(),
     }
 }
+
+/// Clone `url` into `target` without prompting for credentials
+pub fn clone_repo(url: &str, target: &Path) -> Result<(), String> {
+    let output = Command::new("git")
+        .arg("clone")
+        .arg("--quiet")
+        .arg(url)
+        .arg(target)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Fetch all remotes of a repository without prompting, gives up after `timeout`
+pub async fn fetch_remotes_unattended(repo_path: &Path, timeout: Duration) -> Result<(), String> {
+    unattended_git(
+        repo_path,
+        &["fetch", "--all", "--prune", "--quiet"],
+        timeout,
+    )
+    .await
+}
+
+/// Check that `remote` answers, without downloading anything
+pub async fn ls_remote(repo_path: &Path, remote: &str, timeout: Duration) -> Result<(), String> {
+    unattended_git(
+        repo_path,
+        &["ls-remote", "--quiet", remote, "HEAD"],
+        timeout,
+    )
+    .await
+}
+
+/// Run a git command that talks to remotes, giving up after `timeout`
+///
+/// Besides the terminal prompt this also rules out credential manager dialogs
+/// and ssh passphrase prompts, the command either works on its own or fails.
+async fn unattended_git(repo_path: &Path, args: &[&str], timeout: Duration) -> Result<(), String> {
+    let mut command = tokio::process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_path)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+        command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+
+    let output = match tokio::time::timeout(timeout, command.output()).await {
+        Ok(output) => output.map_err(|e| e.to_string())?,
+        Err(_) => return Err(format!("timed out after {}s", timeout.as_secs())),
+    };
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        // Keep the actual errors, not the hints git prints around them
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let errors: Vec<&str> = stderr
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("fatal:") || line.starts_with("error:"))
+            .collect();
+        if errors.is_empty() {
+            Err(stderr.trim().to_string())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// One `git stash` entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    pub commit: String,
+    pub message: String,
+}
+
+/// Stash entries of a repository, newest first
+pub fn list_stashes(repo_path: &Path) -> Vec<StashEntry> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("stash")
+        .arg("list")
+        .arg("--format=%H%x09%gs")
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (commit, message) = line.split_once('\t')?;
+                Some(StashEntry {
+                    commit: commit.to_string(),
+                    message: message.to_string(),
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -1,15 +1,35 @@
+use crate::git::branches::{
+    count_commits, default_branch, list_local_branches, merged_branches, remote_default_branch,
+};
+use crate::git::remote::{list_remote_branches, primary_remote};
 use crate::repo::filters::RepoFilters;
+use serde::{Deserialize, Serialize};
+use std::fs;
 use std::path::Path;
 use std::process::Command;
+use std::time::UNIX_EPOCH;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitStatus {
     pub untracked_files: bool,
     pub unstaged_changes: bool,
     pub ahead_of_remote: bool,
+    pub ahead_commits: u32,
     pub missing_remote: bool,
     pub current_branch: String,
     pub is_default_branch: bool,
+    /// Oldest mtime (unix seconds) of the files with uncommitted changes
+    #[serde(default)]
+    pub dirty_oldest_mtime: Option<i64>,
+    /// Commit time of HEAD, only looked up when the worktree is dirty
+    #[serde(default)]
+    pub head_commit_time: Option<i64>,
+    /// Commits the local default branch is behind the upstream remote's, for forks
+    #[serde(default)]
+    pub behind_upstream: Option<u32>,
+    /// Local branches fully merged into the upstream remote's default branch
+    #[serde(default)]
+    pub merged_upstream: Vec<String>,
 }
 
 impl GitStatus {
@@ -18,9 +38,14 @@ impl GitStatus {
             untracked_files: false,
             unstaged_changes: false,
             ahead_of_remote: false,
+            ahead_commits: 0,
             missing_remote: false,
             current_branch: String::new(),
             is_default_branch: true,
+            dirty_oldest_mtime: None,
+            head_commit_time: None,
+            behind_upstream: None,
+            merged_upstream: Vec::new(),
         }
     }
 }
@@ -36,6 +61,8 @@ pub fn check_git_status(repo_path: &Path, filters: &RepoFilters, debug: bool) ->
             .arg("status")
             .arg("--porcelain")
             .arg("-b") // Include branch info
+            // Don't refresh the index, a read-only check must not touch .git
+            .env("GIT_OPTIONAL_LOCKS", "0")
             .output()
         {
             let output_str = String::from_utf8_lossy(&output.stdout);
@@ -51,10 +78,26 @@ pub fn check_git_status(repo_path: &Path, filters: &RepoFilters, debug: bool) ->
                     .any(|line| line.trim().starts_with("M") || line.trim().starts_with("D"));
             }
 
+            if (filters.check_untracked && status.untracked_files)
+                || (filters.check_unstaged && status.unstaged_changes)
+            {
+                status.dirty_oldest_mtime = output_str
+                    .lines()
+                    .filter_map(porcelain_path)
+                    .filter_map(|path| file_mtime(&repo_path.join(path)))
+                    .min();
+                status.head_commit_time = head_commit_time(repo_path);
+            }
+
             if filters.check_ahead {
-                status.ahead_of_remote = output_str
+                let branch_line = output_str
                     .lines()
-                    .any(|line| line.trim().starts_with("##") && line.contains("[ahead "));
+                    .find(|line| line.trim().starts_with("##"))
+                    .unwrap_or_default();
+                status.ahead_commits = ahead_of_primary(repo_path, branch_line, filters)
+                    .or_else(|| parse_ahead_count(branch_line))
+                    .unwrap_or(0);
+                status.ahead_of_remote = status.ahead_commits > 0;
             }
 
             if debug {
@@ -110,5 +153,202 @@ pub fn check_git_status(repo_path: &Path, filters: &RepoFilters, debug: bool) ->
         }
     }
 
+    // Compare with the repository this one was forked from, if it has a remote for it
+    if filters.check_fork {
+        check_upstream(repo_path, &filters.remotes.upstream, &mut status);
+
+        if debug {
+            if let Some(behind) = status.behind_upstream {
+                println!(
+                    "[-] {} is {} commits behind {}",
+                    repo_path.display(),
+                    behind,
+                    filters.remotes.upstream
+                );
+            }
+        }
+    }
+
     status
 }
+
+fn check_upstream(repo_path: &Path, upstream: &str, status: &mut GitStatus) {
+    let Some(upstream_default) = remote_default_branch(repo_path, upstream) else {
+        return;
+    };
+    let upstream_ref = format!("refs/remotes/{}/{}", upstream, upstream_default);
+    let local_default = default_branch(&list_local_branches(repo_path));
+
+    if let Some(local_default) = &local_default {
+        status.behind_upstream = count_commits(
+            repo_path,
+            &format!("refs/heads/{}", local_default),
+            &upstream_ref,
+        );
+    }
+
+    // A branch without commits of its own, still at its fork point, merged nothing
+    let local_default_ref = local_default
+        .as_ref()
+        .map(|name| format!("refs/heads/{}", name));
+    status.merged_upstream = merged_branches(repo_path, &upstream_ref)
+        .into_iter()
+        .filter(|branch| Some(branch) != local_default.as_ref())
+        .filter(|branch| match &local_default_ref {
+            Some(local_default_ref) => count_commits(
+                repo_path,
+                local_default_ref,
+                &format!("refs/heads/{}", branch),
+            )
+            .is_some_and(|own| own > 0),
+            None => true,
+        })
+        .collect();
+}
+
+/// Parse the commit count out of a `## branch...upstream [ahead N, behind M]` line
+fn parse_ahead_count(branch_line: &str) -> Option<u32> {
+    let rest = &branch_line[branch_line.find("[ahead ")? + "[ahead ".len()..];
+    let end = rest.find([',', ']']).unwrap_or(rest.len());
+    rest[..end].trim().parse().ok()
+}
+
+/// Commits ahead of the same-named branch on the primary remote
+///
+/// `None` when the branch already tracks the primary remote, `git status` knows
+/// best then, or when the primary remote has no such branch.
+fn ahead_of_primary(repo_path: &Path, branch_line: &str, filters: &RepoFilters) -> Option<u32> {
+    let branches = branch_line.trim_start_matches("## ");
+    let branches = branches.split(' ').next().unwrap_or(branches);
+    let (branch, tracking) = match branches.split_once("...") {
+        Some((branch, tracking)) => (branch, Some(tracking)),
+        None => (branches, None),
+    };
+
+    let remote = primary_remote(repo_path, &filters.remotes)?;
+    let prefix = format!("{}/", remote.name);
+    if tracking.is_some_and(|tracking| tracking.starts_with(&prefix)) {
+        return None;
+    }
+    if !list_remote_branches(repo_path, &remote.name)
+        .iter()
+        .any(|remote_branch| remote_branch == branch)
+    {
+        return None;
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("rev-list")
+        .arg("--count")
+        .arg(format!("refs/remotes/{}{}..HEAD", prefix, branch))
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Path of a `git status --porcelain` entry, the destination for renames
+fn porcelain_path(line: &str) -> Option<&str> {
+    if line.starts_with("##") || line.len() < 4 {
+        return None;
+    }
+    let path = &line[3..];
+    let path = path.rsplit(" -> ").next().unwrap_or(path);
+    Some(path.trim_matches('"'))
+}
+
+fn file_mtime(path: &Path) -> Option<i64> {
+    let modified = fs::symlink_metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+fn head_commit_time(repo_path: &Path) -> Option<i64> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("log")
+        .arg("-1")
+        .arg("--format=%ct")
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ahead_count() {
+        assert_eq!(
+            parse_ahead_count("## main...origin/main [ahead 3]"),
+            Some(3)
+        );
+        assert_eq!(
+            parse_ahead_count("## main...origin/main [ahead 12, behind 4]"),
+            Some(12)
+        );
+        assert_eq!(parse_ahead_count("## main...origin/main [behind 4]"), None);
+        assert_eq!(parse_ahead_count("## main...origin/main"), None);
+        assert_eq!(parse_ahead_count("## main"), None);
+    }
+
+    #[test]
+    fn merged_upstream_skips_branches_without_own_commits() {
+        let repo =
+            std::env::temp_dir().join(format!("gitgud-merged-upstream-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args([
+                    "-c",
+                    "user.name=gitgud",
+                    "-c",
+                    "user.email=gitgud@example.com",
+                ])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        git(&["init", "--quiet", "--initial-branch=main"]);
+        git(&["commit", "--quiet", "--allow-empty", "-m", "initial"]);
+        git(&["branch", "fresh"]);
+        git(&["switch", "--quiet", "-c", "feature"]);
+        git(&["commit", "--quiet", "--allow-empty", "-m", "feature"]);
+        git(&["switch", "--quiet", "main"]);
+        // Upstream took the feature, main and fresh are still at the fork point
+        git(&[
+            "update-ref",
+            "refs/remotes/upstream/main",
+            "refs/heads/feature",
+        ]);
+
+        let mut status = GitStatus::new();
+        check_upstream(&repo, "upstream", &mut status);
+        assert_eq!(status.merged_upstream, vec!["feature".to_string()]);
+        assert_eq!(status.behind_upstream, Some(1));
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+
+    #[test]
+    fn parses_porcelain_paths() {
+        assert_eq!(porcelain_path("?? notes.txt"), Some("notes.txt"));
+        assert_eq!(porcelain_path(" M src/main.rs"), Some("src/main.rs"));
+        assert_eq!(porcelain_path("R  old.rs -> new.rs"), Some("new.rs"));
+        assert_eq!(
+            porcelain_path("?? \"with space.txt\""),
+            Some("with space.txt")
+        );
+        assert_eq!(porcelain_path("## main...origin/main"), None);
+        assert_eq!(porcelain_path("??"), None);
+    }
+}
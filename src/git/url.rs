@@ -0,0 +1,143 @@
+/// Where a remote URL points, independent of the protocol used to reach it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RemoteLocation {
+    /// Lowercase host name without user or port, e.g. `github.com`
+    pub host: String,
+    /// Path on the host without `.git`, e.g. `owner/repo` or `group/sub/repo`
+    pub path: String,
+}
+
+/// Parse the URL formats git accepts for remotes
+///
+/// Examples:
+/// - https://github.com/owner/repo.git
+/// - ssh://git@github.com:22/owner/repo.git
+/// - git@github.com:owner/repo.git
+/// - git://github.com/owner/repo.git
+pub fn parse_remote_url(url: &str) -> Option<RemoteLocation> {
+    let url = url.trim();
+
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        // scp-like syntax, a local path never has a colon before its first slash
+        None => {
+            let (authority, path) = url.split_once(':')?;
+            if authority.contains('/') {
+                return None;
+            }
+            (authority, path)
+        }
+    };
+
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_lowercase();
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+
+    Some(RemoteLocation {
+        host,
+        path: path.to_string(),
+    })
+}
+
+/// Key under which different spellings of the same remote compare equal
+///
+/// SSH and HTTPS URLs of a repository, with or without `.git`, in any case,
+/// all end up as `host/owner/repo`.
+pub fn normalize_remote_url(url: &str) -> String {
+    match parse_remote_url(url) {
+        Some(location) => format!("{}/{}", location.host, location.path).to_lowercase(),
+        None => {
+            let url = url.trim().trim_end_matches('/');
+            url.strip_suffix(".git").unwrap_or(url).to_lowercase()
+        }
+    }
+}
+
+/// `url` pointing at `new_path` on the same host, keeping protocol, user and `.git`
+pub fn with_remote_path(url: &str, new_path: &str) -> Option<String> {
+    let url = url.trim();
+    let location = parse_remote_url(url)?;
+    let start = url.rfind(&location.path)?;
+
+    Some(format!(
+        "{}{}{}",
+        &url[..start],
+        new_path,
+        &url[start + location.path.len()..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remote_urls() {
+        let location = |host: &str, path: &str| {
+            Some(RemoteLocation {
+                host: host.to_string(),
+                path: path.to_string(),
+            })
+        };
+
+        assert_eq!(
+            parse_remote_url("https://github.com/owner/repo.git"),
+            location("github.com", "owner/repo")
+        );
+        assert_eq!(
+            parse_remote_url("ssh://git@GitHub.com:22/owner/repo.git"),
+            location("github.com", "owner/repo")
+        );
+        assert_eq!(
+            parse_remote_url("git@github.com:owner/repo"),
+            location("github.com", "owner/repo")
+        );
+        assert_eq!(
+            parse_remote_url("https://gitlab.com/group/sub/repo/"),
+            location("gitlab.com", "group/sub/repo")
+        );
+        assert_eq!(parse_remote_url("/srv/git/repo.git"), None);
+        assert_eq!(parse_remote_url("../repo"), None);
+        assert_eq!(parse_remote_url("https://github.com/repo"), None);
+    }
+
+    #[test]
+    fn normalizes_spellings_of_the_same_remote() {
+        let expected = "github.com/owner/repo";
+        for url in [
+            "https://github.com/owner/repo.git",
+            "https://github.com/Owner/Repo/",
+            "http://user@github.com/owner/repo",
+            "git@github.com:owner/repo.git",
+            "ssh://git@github.com:22/owner/repo",
+            "git://github.com/owner/repo.git",
+        ] {
+            assert_eq!(normalize_remote_url(url), expected, "{}", url);
+        }
+
+        assert_eq!(normalize_remote_url("/srv/git/Repo.git/"), "/srv/git/repo");
+        assert_ne!(
+            normalize_remote_url("git@github.com:owner/repo.git"),
+            normalize_remote_url("git@github.com:owner/other.git")
+        );
+    }
+
+    #[test]
+    fn swaps_the_remote_path() {
+        assert_eq!(
+            with_remote_path("git@github.com:old/repo.git", "new/name"),
+            Some("git@github.com:new/name.git".to_string())
+        );
+        assert_eq!(
+            with_remote_path("https://github.com/old/repo", "new/name"),
+            Some("https://github.com/new/name".to_string())
+        );
+        assert_eq!(with_remote_path("/srv/git/repo", "new/name"), None);
+    }
+}
@@ -1,92 +1,381 @@
-use crate::git::remote::get_remote_url;
-use crate::github::pr::PullRequestInfo;
-use octocrab::Octocrab;
+use crate::config::RemoteNames;
+use crate::git::branches::{count_commits, remote_default_branch};
+use crate::git::remote::{list_remote_branches, list_remotes, primary_remote};
+use crate::git::url::parse_remote_url;
+use crate::github::client::{GithubClient, GithubError};
+use crate::github::pr::{PullRequestInfo, PullRequestListing};
+use octocrab::models::commits::CommitComparison;
+use octocrab::models::pulls::PullRequest;
+use octocrab::models::Repository;
+use std::collections::HashSet;
 use std::path::Path;
 
+/// Open upstream pull requests looked through for ours, most recently updated first
+const UPSTREAM_PRS_SEARCHED: usize = 300;
+
+/// Where a fork comes from and what is going on there
+#[derive(Debug, Clone, Default)]
+pub struct UpstreamInfo {
+    /// `owner/repo` of the upstream repository
+    pub repo: String,
+    /// Open pull requests to the upstream repository made from our fork
+    pub prs: Vec<PullRequestInfo>,
+    /// Commits our default branch is behind upstream's, only asked for
+    /// when there is no local upstream remote to compare with
+    pub behind_by: Option<u32>,
+}
+
+/// Owner and name of the repository behind the primary remote, if it is on GitHub
+pub fn github_repo(
+    repo_path: &Path,
+    remotes: &RemoteNames,
+) -> Result<Option<(String, String)>, GithubError> {
+    // PRs are opened from the remote we push to
+    match primary_remote(repo_path, remotes) {
+        Some(remote) => parse_github_url(&remote.url)
+            .map(Some)
+            .map_err(|e| GithubError::InvalidUrl(e.to_string())),
+        None => Ok(None), // No remote URL found
+    }
+}
+
+/// Open pull requests, at most `max_prs`, and how many there are in total
+async fn list_open_prs(
+    github: &GithubClient,
+    owner: &str,
+    repo: &str,
+    max_prs: usize,
+) -> Result<(Vec<PullRequest>, usize), GithubError> {
+    github
+        .get_list(
+            &format!("/repos/{}/{}/pulls?state=open", owner, repo),
+            max_prs,
+        )
+        .await
+}
+
+fn pull_request_info(pull: PullRequest, owner: &str, repo: &str) -> PullRequestInfo {
+    PullRequestInfo {
+        number: pull.number,
+        title: pull.title.expect("no Pull Request Title found ??"),
+        branch: pull.head.ref_field,
+        is_draft: pull.draft.unwrap_or(false),
+        review_decision: None,
+        ci_status: None,
+        url: pull.html_url.map(|url| url.to_string()).unwrap_or_else(|| {
+            format!("https://github.com/{}/{}/pull/{}", owner, repo, pull.number)
+        }),
+    }
+}
+
 /// Fetch GitHub PR information for a repository
 pub async fn fetch_github_prs(
     repo_path: &Path,
-    github_token: Option<&str>,
+    remotes: &RemoteNames,
+    github: &GithubClient,
+    max_prs: usize,
     debug: bool,
-) -> Result<Vec<PullRequestInfo>, Box<dyn std::error::Error>> {
-    // Get remote URL
-    let remote_url = match get_remote_url(repo_path) {
-        Some(url) => url,
-        None => return Ok(Vec::new()), // No remote URL found
+) -> Result<PullRequestListing, GithubError> {
+    let Some((owner, repo)) = github_repo(repo_path, remotes)? else {
+        return Ok(PullRequestListing::default());
     };
 
-    // Parse the GitHub repo URL to extract owner and repo
-    let (owner, repo) = parse_github_url(&remote_url)?;
-
     if debug {
         println!("[-] Fetching PRs for {}/{}", owner, repo);
     }
 
-    // Create GitHub client with token if available
-    let octocrab = match github_token {
-        Some(token) => Octocrab::builder()
-            .personal_token(token.to_string())
-            .build()?,
-        None => Octocrab::builder().build()?,
+    let (pulls, total) = list_open_prs(github, &owner, &repo, max_prs).await?;
+
+    Ok(PullRequestListing {
+        prs: pulls
+            .into_iter()
+            .map(|pull| pull_request_info(pull, &owner, &repo))
+            .collect(),
+        total,
+    })
+}
+
+/// Find the repository a fork was made from and our open pull requests there
+///
+/// The upstream comes from the local upstream remote when there is one, otherwise
+/// from the `parent` GitHub reports for the repository behind the primary remote.
+/// Returns `None` for repositories that are not forks.
+pub async fn fetch_upstream_info(
+    repo_path: &Path,
+    remotes: &RemoteNames,
+    github: &GithubClient,
+    max_prs: usize,
+    debug: bool,
+) -> Result<Option<UpstreamInfo>, GithubError> {
+    let Some((owner, repo)) = github_repo(repo_path, remotes)? else {
+        return Ok(None);
+    };
+
+    let local_upstream = list_remotes(repo_path)
+        .into_iter()
+        .find(|remote| remote.name == remotes.upstream)
+        .and_then(|remote| parse_github_url(&remote.url).ok());
+    let fork_branches =
+        fork_branches_with_own_commits(repo_path, remotes, local_upstream.is_some());
+
+    // Without a local upstream remote the fork has to be compared on GitHub
+    let mut compare = None;
+    let (upstream_owner, upstream_repo) = match local_upstream {
+        Some(upstream) => upstream,
+        None => {
+            let fork: Repository = github.get(&format!("/repos/{}/{}", owner, repo)).await?;
+            let Some(parent) = fork.parent else {
+                return Ok(None);
+            };
+            let Some((parent_owner, parent_repo)) = parent
+                .full_name
+                .as_deref()
+                .and_then(|name| name.split_once('/'))
+                .map(|(owner, repo)| (owner.to_string(), repo.to_string()))
+            else {
+                return Ok(None);
+            };
+            compare = parent.default_branch.zip(fork.default_branch);
+            (parent_owner, parent_repo)
+        }
     };
 
-    // Fetch open pull requests
-    let pulls = octocrab
-        .pulls(owner, repo)
-        .list()
-        .state(octocrab::params::State::Open)
-        .send()
-        .await?;
-
-    let mut pr_info = Vec::new();
-    for pull in pulls.items {
-        pr_info.push(PullRequestInfo {
-            number: pull.number,
-            title: pull.title.expect("no Pull Request Title found ??"),
-            branch: pull.head.ref_field,
-            is_draft: pull.draft.unwrap_or(false),
-        });
+    if debug {
+        println!(
+            "[-] Fetching PRs from {}/{} to upstream {}/{}",
+            owner, repo, upstream_owner, upstream_repo
+        );
     }
 
-    Ok(pr_info)
+    // Only branches with commits of their own can be behind a pull request, and
+    // one listing of upstream's pull requests beats a request per branch
+    let mut prs = Vec::new();
+    if !fork_branches.is_empty() {
+        let (pulls, _) = github
+            .get_list::<PullRequest>(
+                &format!(
+                    "/repos/{}/{}/pulls?state=open&sort=updated&direction=desc",
+                    upstream_owner, upstream_repo
+                ),
+                UPSTREAM_PRS_SEARCHED,
+            )
+            .await?;
+        prs = pulls
+            .into_iter()
+            .filter(|pull| {
+                pull.head
+                    .label
+                    .as_deref()
+                    .and_then(|label| label.split_once(':'))
+                    .is_some_and(|(head_owner, branch)| {
+                        head_owner.eq_ignore_ascii_case(&owner) && fork_branches.contains(branch)
+                    })
+            })
+            .take(max_prs)
+            .map(|pull| pull_request_info(pull, &upstream_owner, &upstream_repo))
+            .collect();
+    }
+
+    let behind_by = match compare {
+        Some((upstream_default, fork_default)) => {
+            let comparison: CommitComparison = github
+                .get(&format!(
+                    "/repos/{}/{}/compare/{}...{}:{}",
+                    upstream_owner, upstream_repo, upstream_default, owner, fork_default
+                ))
+                .await?;
+            Some(comparison.behind_by.max(0) as u32)
+        }
+        None => None,
+    };
+
+    Ok(Some(UpstreamInfo {
+        repo: format!("{}/{}", upstream_owner, upstream_repo),
+        prs,
+        behind_by,
+    }))
+}
+
+/// Branches of the primary remote with commits that are not on the default branch
+///
+/// The default branch is upstream's when there is a local upstream remote to
+/// compare with, otherwise the fork's own.
+fn fork_branches_with_own_commits(
+    repo_path: &Path,
+    remotes: &RemoteNames,
+    has_upstream_remote: bool,
+) -> HashSet<String> {
+    let Some(fork) = primary_remote(repo_path, remotes) else {
+        return HashSet::new();
+    };
+    let base = if has_upstream_remote {
+        &remotes.upstream
+    } else {
+        &fork.name
+    };
+    let Some(base_default) = remote_default_branch(repo_path, base) else {
+        return HashSet::new();
+    };
+    let base_ref = format!("refs/remotes/{}/{}", base, base_default);
+
+    list_remote_branches(repo_path, &fork.name)
+        .into_iter()
+        .filter(|branch| {
+            count_commits(
+                repo_path,
+                &base_ref,
+                &format!("refs/remotes/{}/{}", fork.name, branch),
+            )
+            .is_some_and(|own| own > 0)
+        })
+        .collect()
+}
+
+/// What GitHub knows about the repository behind a remote URL
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GithubRepoState {
+    /// `owner/repo` GitHub redirects to, when it was renamed or transferred
+    pub moved_to: Option<String>,
+    pub archived: bool,
+    /// GitHub answered 404, the repository is gone or we lost access to it
+    pub missing: bool,
+}
+
+/// Look up the repository behind `url`, `None` when it is not a GitHub URL
+pub async fn fetch_github_repo_state(
+    url: &str,
+    github: &GithubClient,
+    debug: bool,
+) -> Result<Option<GithubRepoState>, GithubError> {
+    let Ok((owner, repo)) = parse_github_url(url) else {
+        return Ok(None);
+    };
+
+    if debug {
+        println!("[-] Looking up {}/{} on GitHub", owner, repo);
+    }
+
+    // Renamed and transferred repositories redirect to their new location
+    let route = format!("/repos/{}/{}", owner, repo);
+    let repository: Repository = match github.get(&route).await {
+        Ok(repository) => repository,
+        Err(GithubError::Api(octocrab::Error::GitHub { source, .. }))
+            if source.status_code.as_u16() == 404 =>
+        {
+            return Ok(Some(GithubRepoState {
+                missing: true,
+                ..GithubRepoState::default()
+            }));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let moved_to = repository
+        .full_name
+        .filter(|name| !name.eq_ignore_ascii_case(&format!("{}/{}", owner, repo)));
+
+    Ok(Some(GithubRepoState {
+        moved_to,
+        archived: repository.archived.unwrap_or(false),
+        missing: false,
+    }))
 }
 
 /// Helper function to parse GitHub URL
 pub fn parse_github_url(url: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
-    // Handle various GitHub URL formats
-    // Examples:
-    // - https://github.com/owner/repo.git
-    // - git@github.com:owner/repo.git
-    // - git://github.com/owner/repo.git
-
-    let url = url.trim();
-
-    // HTTPS format
-    if let Some(path) = url.strip_prefix("https://github.com/") {
-        let path = path.strip_suffix(".git").unwrap_or(path);
-        let parts: Vec<&str> = path.split('/').collect();
-        if parts.len() >= 2 {
-            return Ok((parts[0].to_string(), parts[1].to_string()));
-        }
+    let location = parse_remote_url(url)
+        .filter(|location| location.host == "github.com")
+        .ok_or("Unable to parse GitHub URL")?;
+
+    let mut parts = location.path.split('/');
+    match (parts.next(), parts.next()) {
+        (Some(owner), Some(repo)) => Ok((owner.to_string(), repo.to_string())),
+        _ => Err("Unable to parse GitHub URL".into()),
     }
+}
 
-    // SSH format
-    if let Some(path) = url.strip_prefix("git@github.com:") {
-        let path = path.strip_suffix(".git").unwrap_or(path);
-        let parts: Vec<&str> = path.split('/').collect();
-        if parts.len() >= 2 {
-            return Ok((parts[0].to_string(), parts[1].to_string()));
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .env("GIT_AUTHOR_NAME", "gitgud")
+            .env("GIT_AUTHOR_EMAIL", "gitgud@example.com")
+            .env("GIT_COMMITTER_NAME", "gitgud")
+            .env("GIT_COMMITTER_EMAIL", "gitgud@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
     }
 
-    // Git protocol format
-    if let Some(path) = url.strip_prefix("git://github.com/") {
-        let path = path.strip_suffix(".git").unwrap_or(path);
-        let parts: Vec<&str> = path.split('/').collect();
-        if parts.len() >= 2 {
-            return Ok((parts[0].to_string(), parts[1].to_string()));
-        }
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gitgud-api-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
     }
 
-    Err("Unable to parse GitHub URL".into())
+    #[test]
+    fn only_fork_branches_with_their_own_commits_are_looked_up() {
+        let repo = scratch_dir("fork-branches");
+        git(&repo, &["init", "--quiet", "--initial-branch", "main"]);
+        git(
+            &repo,
+            &[
+                "remote",
+                "add",
+                "origin",
+                "https://github.com/me/project.git",
+            ],
+        );
+        git(
+            &repo,
+            &["commit", "--quiet", "--allow-empty", "-m", "initial"],
+        );
+        git(&repo, &["update-ref", "refs/remotes/origin/main", "HEAD"]);
+        git(&repo, &["update-ref", "refs/remotes/origin/stale", "HEAD"]);
+        git(
+            &repo,
+            &["commit", "--quiet", "--allow-empty", "-m", "feature"],
+        );
+        git(
+            &repo,
+            &["update-ref", "refs/remotes/origin/feature", "HEAD"],
+        );
+
+        let remotes = RemoteNames::default();
+        assert_eq!(
+            fork_branches_with_own_commits(&repo, &remotes, false),
+            HashSet::from(["feature".to_string()])
+        );
+
+        // Against upstream's default branch, our main has commits of its own too
+        git(
+            &repo,
+            &[
+                "remote",
+                "add",
+                "upstream",
+                "https://github.com/them/project.git",
+            ],
+        );
+        git(
+            &repo,
+            &["update-ref", "refs/remotes/upstream/main", "HEAD~1"],
+        );
+        git(&repo, &["update-ref", "refs/remotes/origin/main", "HEAD"]);
+        assert_eq!(
+            fork_branches_with_own_commits(&repo, &remotes, true),
+            HashSet::from(["feature".to_string(), "main".to_string()])
+        );
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
 }
@@ -0,0 +1,494 @@
+use chrono::{Local, TimeZone};
+use octocrab::service::middleware::retry::RetryConfig;
+use octocrab::Octocrab;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Retries of a single rate limited request before giving up on it
+const MAX_RETRIES: u32 = 3;
+/// Longest we wait for the rate limit before skipping the remaining lookups
+const MAX_WAIT: Duration = Duration::from_secs(60);
+/// Largest page size GitHub hands out
+const MAX_PER_PAGE: usize = 100;
+/// First backoff when GitHub doesn't say how long to wait, doubled on every retry
+const BACKOFF: Duration = Duration::from_secs(5);
+/// Where the GitHub API lives
+const GITHUB_API: &str = "https://api.github.com";
+
+#[derive(Debug, Error)]
+pub enum GithubError {
+    #[error("rate limited{}", reset_suffix(*.reset))]
+    RateLimited { reset: Option<i64> },
+    #[error("{0}")]
+    Api(#[from] octocrab::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Graphql(String),
+    #[error("{0}")]
+    InvalidUrl(String),
+}
+
+impl GithubError {
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, GithubError::RateLimited { .. })
+    }
+}
+
+fn reset_suffix(reset: Option<i64>) -> String {
+    reset
+        .map(|reset| format!(" until {}", format_reset(reset)))
+        .unwrap_or_default()
+}
+
+fn format_reset(reset: i64) -> String {
+    Local
+        .timestamp_opt(reset, 0)
+        .single()
+        .map(|time| time.format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| reset.to_string())
+}
+
+/// Answer to a GraphQL query, `data` can be partial when there are `errors`
+#[derive(Debug, Deserialize)]
+pub struct GraphqlResponse {
+    pub data: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(default)]
+    pub errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphqlError {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub message: String,
+    /// Where in the query the error happened, starting with the alias
+    #[serde(default)]
+    pub path: Vec<serde_json::Value>,
+}
+
+/// Body of a successful response and the pagination links that came with it
+struct Page {
+    body: String,
+    next: Option<String>,
+    last: Option<String>,
+}
+
+/// URL of the `rel` entry in a `Link` header
+///
+/// `<https://api.github.com/repositories/1/pulls?page=2>; rel="next", <...>; rel="last"`
+fn link_target(header: &str, rel: &str) -> Option<String> {
+    header.split(',').find_map(|entry| {
+        let (url, params) = entry.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == format!("rel=\"{}\"", rel))
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// The `page` query parameter of a pagination link
+fn page_number(url: &str) -> Option<usize> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|param| param.strip_prefix("page="))
+        .and_then(|page| page.parse().ok())
+}
+
+/// What we know about the rate limit, from the headers of the last response
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub retries: u32,
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    /// Unix time the limit resets at
+    pub reset: Option<i64>,
+    /// Set once waiting for the limit took too long, later requests are skipped
+    pub exhausted: bool,
+}
+
+impl RateLimit {
+    /// One line summary for `--debug`
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "GitHub API: {} requests, {} retries",
+            self.requests, self.retries
+        );
+        if let (Some(remaining), Some(limit)) = (self.remaining, self.limit) {
+            summary.push_str(&format!(", {}/{} remaining", remaining, limit));
+        }
+        if let Some(reset) = self.reset {
+            summary.push_str(&format!(", resets at {}", format_reset(reset)));
+        }
+        summary
+    }
+
+    fn seconds_until_reset(&self) -> Option<u64> {
+        let reset = self.reset?;
+        let now = chrono::Utc::now().timestamp();
+        Some((reset - now).max(0) as u64 + 1)
+    }
+}
+
+/// GitHub client shared by every lookup of a run
+///
+/// Keeps track of the rate limit headers so that once the limit is used up
+/// requests wait for the reset, or fail fast with `GithubError::RateLimited`
+/// when that is too far away, instead of all hitting GitHub for a 403.
+#[derive(Debug, Clone)]
+pub struct GithubClient {
+    octocrab: Octocrab,
+    rate_limit: Arc<Mutex<RateLimit>>,
+    authenticated: bool,
+}
+
+impl GithubClient {
+    pub fn new(github_token: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_base_uri(github_token, GITHUB_API)
+    }
+
+    /// Client for the API at `base_uri` instead of api.github.com
+    pub fn with_base_uri(
+        github_token: Option<&str>,
+        base_uri: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // octocrab retries 429s right away, backing off is up to `get`
+        let builder = Octocrab::builder()
+            .add_retry_config(RetryConfig::None)
+            .base_uri(base_uri)?;
+
+        // Create GitHub client with token if available
+        let octocrab = match github_token {
+            Some(token) => builder.personal_token(token.to_string()).build()?,
+            None => builder.build()?,
+        };
+
+        Ok(Self {
+            octocrab,
+            rate_limit: Arc::new(Mutex::new(RateLimit::default())),
+            authenticated: github_token.is_some(),
+        })
+    }
+
+    /// Whether requests carry a token, without one private repositories look missing
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
+
+    pub fn rate_limit(&self) -> RateLimit {
+        self.rate_limit.lock().unwrap().clone()
+    }
+
+    /// `GET` an API route like `/repos/owner/repo`, waiting out the rate limit if it's close
+    pub async fn get<T: DeserializeOwned>(&self, route: &str) -> Result<T, GithubError> {
+        let page = self.fetch(route, None).await?;
+        Ok(serde_json::from_str(&page.body)?)
+    }
+
+    /// `GET` a list route page by page, keeping at most `cap` items
+    ///
+    /// Returns the items and the length of the whole list. When the cap cuts the
+    /// list short that takes one more request, for the last page.
+    pub async fn get_list<T: DeserializeOwned>(
+        &self,
+        route: &str,
+        cap: usize,
+    ) -> Result<(Vec<T>, usize), GithubError> {
+        let per_page = cap.clamp(1, MAX_PER_PAGE);
+        let separator = if route.contains('?') { '&' } else { '?' };
+        let mut next = Some(format!("{}{}per_page={}", route, separator, per_page));
+        let mut items = Vec::new();
+        let mut pages = 0;
+        let mut last = None;
+        let mut cut_short = false;
+
+        while let Some(route) = next.take() {
+            let page = self.fetch(&route, None).await?;
+            items.extend(serde_json::from_str::<Vec<T>>(&page.body)?);
+            pages += 1;
+            // The last page has no `last` link of its own
+            last = page.last.or(last);
+
+            if items.len() < cap {
+                next = page.next;
+            } else {
+                cut_short = page.next.is_some();
+            }
+        }
+
+        let last_page = last.as_deref().and_then(page_number);
+        let total = match (&last, last_page) {
+            // Only the last page tells how long the list is
+            (Some(last), Some(last_page)) if cut_short && last_page > pages => {
+                let page = self.fetch(last, None).await?;
+                let tail: Vec<serde_json::Value> = serde_json::from_str(&page.body)?;
+                (last_page - 1) * per_page + tail.len()
+            }
+            _ => items.len(),
+        };
+
+        items.truncate(cap);
+        Ok((items, total))
+    }
+
+    /// Run a GraphQL query, returns its `data`
+    ///
+    /// GraphQL reports most problems next to partial data, those are left to the
+    /// caller. Only running out of the rate limit fails the whole query.
+    pub async fn graphql(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<GraphqlResponse, GithubError> {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+        let page = self.fetch("/graphql", Some(&body)).await?;
+        let response: GraphqlResponse = serde_json::from_str(&page.body)?;
+
+        if response
+            .errors
+            .iter()
+            .any(|error| error.kind.as_deref() == Some("RATE_LIMITED"))
+        {
+            let mut rate_limit = self.rate_limit.lock().unwrap();
+            rate_limit.exhausted = true;
+            return Err(GithubError::RateLimited {
+                reset: rate_limit.reset,
+            });
+        }
+        Ok(response)
+    }
+
+    /// Send a request, a `POST` when there is a body, and read the whole response,
+    /// retrying while rate limited
+    async fn fetch(
+        &self,
+        route: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<Page, GithubError> {
+        let mut attempt = 0;
+
+        loop {
+            self.wait_for_reset().await?;
+
+            let response = match body {
+                Some(body) => self.octocrab._post(route, Some(body)).await?,
+                None => self.octocrab._get(route).await?,
+            };
+            let headers = response.headers();
+            let header = |name: &str| {
+                headers
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<i64>().ok())
+            };
+            let wait = self.record(response.status().as_u16(), header, attempt);
+
+            let Some(wait) = wait else {
+                let link = |rel: &str| {
+                    response
+                        .headers()
+                        .get("link")
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| link_target(value, rel))
+                };
+                let (next, last) = (link("next"), link("last"));
+                let response = octocrab::map_github_error(response).await?;
+                return Ok(Page {
+                    body: self.octocrab.body_to_string(response).await?,
+                    next,
+                    last,
+                });
+            };
+
+            {
+                let mut rate_limit = self.rate_limit.lock().unwrap();
+                if attempt >= MAX_RETRIES || wait > MAX_WAIT {
+                    rate_limit.exhausted = true;
+                    return Err(GithubError::RateLimited {
+                        reset: rate_limit.reset,
+                    });
+                }
+                rate_limit.retries += 1;
+            }
+
+            attempt += 1;
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Hold back while the limit is used up and resets soon, fail once it's given up on
+    async fn wait_for_reset(&self) -> Result<(), GithubError> {
+        let wait = {
+            let rate_limit = self.rate_limit.lock().unwrap();
+            if rate_limit.exhausted {
+                return Err(GithubError::RateLimited {
+                    reset: rate_limit.reset,
+                });
+            }
+            match rate_limit.remaining {
+                Some(0) => rate_limit.seconds_until_reset().map(Duration::from_secs),
+                _ => None,
+            }
+        };
+
+        match wait {
+            Some(wait) if wait > MAX_WAIT => {
+                let mut rate_limit = self.rate_limit.lock().unwrap();
+                rate_limit.exhausted = true;
+                Err(GithubError::RateLimited {
+                    reset: rate_limit.reset,
+                })
+            }
+            Some(wait) => {
+                tokio::time::sleep(wait).await;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Remember the rate limit headers, returns how long to wait if the request was refused
+    fn record(
+        &self,
+        status: u16,
+        header: impl Fn(&str) -> Option<i64>,
+        attempt: u32,
+    ) -> Option<Duration> {
+        let mut rate_limit = self.rate_limit.lock().unwrap();
+        rate_limit.requests += 1;
+        if let Some(limit) = header("x-ratelimit-limit") {
+            rate_limit.limit = Some(limit as u32);
+        }
+        if let Some(remaining) = header("x-ratelimit-remaining") {
+            rate_limit.remaining = Some(remaining as u32);
+        }
+        if let Some(reset) = header("x-ratelimit-reset") {
+            rate_limit.reset = Some(reset);
+        }
+
+        // Secondary limits come with Retry-After, the primary one runs out of requests
+        let retry_after = header("retry-after");
+        let limited = status == 429
+            || status == 403 && (retry_after.is_some() || rate_limit.remaining == Some(0));
+        if !limited {
+            return None;
+        }
+
+        Some(match retry_after {
+            Some(seconds) => Duration::from_secs(seconds.max(0) as u64),
+            None => match rate_limit.remaining {
+                Some(0) => rate_limit
+                    .seconds_until_reset()
+                    .map(Duration::from_secs)
+                    .unwrap_or(BACKOFF * 2u32.pow(attempt)),
+                _ => BACKOFF * 2u32.pow(attempt),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve `/items/<count>` as the numbers 1 to count, paginated like GitHub
+    /// with `per_page` and `page` and `Link` headers
+    fn stub_list_api() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let base = format!("http://{}", address);
+        let links_base = base.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let target = request_line.split(' ').nth(1).unwrap_or_default();
+                let (path, query) = target.split_once('?').unwrap_or((target, ""));
+                let count: usize = path.trim_start_matches("/items/").parse().unwrap();
+                let param = |name: &str| {
+                    query
+                        .split('&')
+                        .find_map(|param| param.strip_prefix(&format!("{}=", name)))
+                        .and_then(|value| value.parse::<usize>().ok())
+                };
+                let per_page = param("per_page").unwrap_or(30);
+                let page = param("page").unwrap_or(1);
+                let last = count.div_ceil(per_page).max(1);
+
+                let items: Vec<usize> =
+                    ((page - 1) * per_page + 1..=count).take(per_page).collect();
+                let body = serde_json::to_string(&items).unwrap();
+                let link = |rel: &str, page: usize| {
+                    format!(
+                        "<{}{}?per_page={}&page={}>; rel=\"{}\"",
+                        links_base, path, per_page, page, rel
+                    )
+                };
+                let mut links = Vec::new();
+                if page < last {
+                    links.push(link("next", page + 1));
+                    links.push(link("last", last));
+                }
+
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nLink: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    links.join(", "),
+                    body.len(),
+                    body
+                );
+            }
+        });
+
+        base
+    }
+
+    #[tokio::test]
+    async fn lists_are_paginated_capped_and_counted() {
+        let github = GithubClient::with_base_uri(None, &stub_list_api()).unwrap();
+
+        // Everything fits on one page
+        let (items, total) = github.get_list::<usize>("/items/7", 30).await.unwrap();
+        assert_eq!(items, (1..=7).collect::<Vec<_>>());
+        assert_eq!(total, 7);
+        assert_eq!(github.rate_limit().requests, 1);
+
+        // Cut short after the first page, the last page tells the total
+        let (items, total) = github.get_list::<usize>("/items/7", 3).await.unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(total, 7);
+        assert_eq!(github.rate_limit().requests, 3);
+
+        // Over several full pages and truncated to the cap
+        let (items, total) = github.get_list::<usize>("/items/250", 150).await.unwrap();
+        assert_eq!(items, (1..=150).collect::<Vec<_>>());
+        assert_eq!(total, 250);
+        assert_eq!(github.rate_limit().requests, 6);
+
+        // The cap falls on the end of the list, no extra request for the total
+        let (items, total) = github.get_list::<usize>("/items/200", 200).await.unwrap();
+        assert_eq!(items.len(), 200);
+        assert_eq!(total, 200);
+        assert_eq!(github.rate_limit().requests, 8);
+    }
+}
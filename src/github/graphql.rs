@@ -0,0 +1,314 @@
+use crate::config::RemoteNames;
+use crate::github::api::github_repo;
+use crate::github::client::{GithubClient, GithubError, GraphqlResponse};
+use crate::github::pr::{PullRequestInfo, PullRequestListing};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Repositories asked for in a single GraphQL request
+const BATCH_SIZE: usize = 50;
+/// Largest `first` GitHub accepts on a connection
+const MAX_PAGE_SIZE: usize = 100;
+
+/// What is asked for every repository, the `pullRequests` connection arguments go in `{args}`
+const REPOSITORY_FIELDS: &str =
+    "pullRequests(states: OPEN, {args}, orderBy: {field: CREATED_AT, direction: DESC}) {
+      totalCount
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title url isDraft headRefName reviewDecision
+        commits(last: 1) { nodes { commit { statusCheckRollup { state } } } }
+      }
+    }";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryNode {
+    pull_requests: PullRequestConnection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequestConnection {
+    total_count: usize,
+    page_info: PageInfo,
+    nodes: Vec<PullRequestNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequestNode {
+    number: u64,
+    title: String,
+    url: String,
+    is_draft: bool,
+    head_ref_name: String,
+    review_decision: Option<String>,
+    commits: CommitConnection,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitConnection {
+    nodes: Vec<CommitNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitNode {
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Commit {
+    status_check_rollup: Option<StatusCheckRollup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusCheckRollup {
+    state: String,
+}
+
+impl From<PullRequestNode> for PullRequestInfo {
+    fn from(node: PullRequestNode) -> Self {
+        let ci_status = node
+            .commits
+            .nodes
+            .into_iter()
+            .next()
+            .and_then(|node| node.commit.status_check_rollup)
+            .map(|rollup| rollup.state);
+
+        PullRequestInfo {
+            number: node.number,
+            title: node.title,
+            branch: node.head_ref_name,
+            is_draft: node.is_draft,
+            url: node.url,
+            review_decision: node.review_decision,
+            ci_status,
+        }
+    }
+}
+
+/// One aliased `repository` field per repository, `r0`, `r1`, ...
+fn batch_query(count: usize, first: usize) -> String {
+    let params: Vec<String> = (0..count)
+        .map(|i| format!("$owner{i}: String!, $name{i}: String!"))
+        .collect();
+    let fields = REPOSITORY_FIELDS.replace("{args}", &format!("first: {}", first));
+    let repositories: Vec<String> = (0..count)
+        .map(|i| {
+            format!("  r{i}: repository(owner: $owner{i}, name: $name{i}) {{\n    {fields}\n  }}")
+        })
+        .collect();
+
+    format!(
+        "query({}) {{\n{}\n}}",
+        params.join(", "),
+        repositories.join("\n")
+    )
+}
+
+/// The page after `$after` for a single repository, aliased `r0` like in a batch
+fn next_page_query(first: usize) -> String {
+    let fields = REPOSITORY_FIELDS.replace("{args}", &format!("first: {}, after: $after", first));
+    format!(
+        "query($owner0: String!, $name0: String!, $after: String!) {{\n  r0: repository(owner: $owner0, name: $name0) {{\n    {}\n  }}\n}}",
+        fields
+    )
+}
+
+/// The repository under `alias`, or the error GraphQL reported for it
+fn repository(
+    response: &mut GraphqlResponse,
+    alias: &str,
+) -> Result<PullRequestConnection, GithubError> {
+    let node = response
+        .data
+        .as_mut()
+        .and_then(|data| data.remove(alias))
+        .filter(|node| !node.is_null());
+
+    match node {
+        Some(node) => Ok(serde_json::from_value::<RepositoryNode>(node)?.pull_requests),
+        None => {
+            let message = response
+                .errors
+                .iter()
+                .find(|error| error.path.first().and_then(|p| p.as_str()) == Some(alias))
+                .map(|error| error.message.clone())
+                .unwrap_or_else(|| "repository missing from GraphQL response".to_string());
+            Err(GithubError::Graphql(message))
+        }
+    }
+}
+
+/// Same error for every repository of a failed batch
+fn batch_error(error: &GithubError) -> GithubError {
+    match error {
+        GithubError::RateLimited { reset } => GithubError::RateLimited { reset: *reset },
+        error => GithubError::Graphql(error.to_string()),
+    }
+}
+
+/// Read the pages beyond the first one until `max_prs` are listed
+async fn remaining_pages(
+    github: &GithubClient,
+    owner: &str,
+    name: &str,
+    mut connection: PullRequestConnection,
+    max_prs: usize,
+) -> Result<PullRequestListing, GithubError> {
+    let total = connection.total_count;
+    let mut prs: Vec<PullRequestInfo> = Vec::new();
+
+    loop {
+        prs.extend(connection.nodes.into_iter().map(PullRequestInfo::from));
+
+        let after = match connection.page_info.end_cursor {
+            Some(cursor) if connection.page_info.has_next_page && prs.len() < max_prs => cursor,
+            _ => break,
+        };
+        let first = (max_prs - prs.len()).min(MAX_PAGE_SIZE);
+        let variables = serde_json::json!({ "owner0": owner, "name0": name, "after": after });
+        let mut response = github.graphql(&next_page_query(first), variables).await?;
+        connection = repository(&mut response, "r0")?;
+    }
+
+    prs.truncate(max_prs);
+    Ok(PullRequestListing { prs, total })
+}
+
+/// Open pull requests of many repositories, `BATCH_SIZE` per GraphQL request
+///
+/// Yields the same listings as `fetch_github_prs` for every repository, plus review
+/// decision and CI status, in one round trip for up to `BATCH_SIZE` repositories.
+pub async fn fetch_github_prs_batched(
+    repos: &[PathBuf],
+    remotes: &RemoteNames,
+    github: &GithubClient,
+    max_prs: usize,
+    debug: bool,
+) -> HashMap<PathBuf, Result<PullRequestListing, GithubError>> {
+    let mut results = HashMap::new();
+    let mut on_github = Vec::new();
+
+    for repo_path in repos {
+        match github_repo(repo_path, remotes) {
+            Ok(Some((owner, name))) => on_github.push((repo_path.clone(), owner, name)),
+            Ok(None) => {
+                results.insert(repo_path.clone(), Ok(PullRequestListing::default()));
+            }
+            Err(e) => {
+                results.insert(repo_path.clone(), Err(e));
+            }
+        }
+    }
+
+    let first = max_prs.clamp(1, MAX_PAGE_SIZE);
+    for batch in on_github.chunks(BATCH_SIZE) {
+        if debug {
+            println!(
+                "[-] Fetching PRs for {} repositories via GraphQL",
+                batch.len()
+            );
+        }
+
+        let mut variables = serde_json::Map::new();
+        for (i, (_, owner, name)) in batch.iter().enumerate() {
+            variables.insert(format!("owner{}", i), owner.clone().into());
+            variables.insert(format!("name{}", i), name.clone().into());
+        }
+
+        let query = batch_query(batch.len(), first);
+        let mut response = match github.graphql(&query, variables.into()).await {
+            Ok(response) => response,
+            Err(e) => {
+                for (repo_path, _, _) in batch {
+                    results.insert(repo_path.clone(), Err(batch_error(&e)));
+                }
+                continue;
+            }
+        };
+
+        for (i, (repo_path, owner, name)) in batch.iter().enumerate() {
+            let listing = match repository(&mut response, &format!("r{}", i)) {
+                Ok(connection) => remaining_pages(github, owner, name, connection, max_prs).await,
+                Err(e) => Err(e),
+            };
+            results.insert(repo_path.clone(), listing);
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_query_aliases_every_repository() {
+        let query = batch_query(3, 25);
+
+        assert!(query.starts_with(
+            "query($owner0: String!, $name0: String!, $owner1: String!, $name1: String!, \
+             $owner2: String!, $name2: String!) {"
+        ));
+        for i in 0..3 {
+            assert!(query.contains(&format!(
+                "r{i}: repository(owner: $owner{i}, name: $name{i}) {{"
+            )));
+        }
+        assert!(!query.contains("r3:"));
+        assert_eq!(
+            query
+                .matches("pullRequests(states: OPEN, first: 25,")
+                .count(),
+            3
+        );
+        assert!(!query.contains("{args}"));
+        assert_eq!(query.matches('{').count(), query.matches('}').count());
+    }
+
+    fn response(value: serde_json::Value) -> GraphqlResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn repository_errors_belong_to_their_alias() {
+        let mut response = response(serde_json::json!({
+            "data": {
+                "r0": {
+                    "pullRequests": {
+                        "totalCount": 0,
+                        "pageInfo": { "hasNextPage": false, "endCursor": null },
+                        "nodes": []
+                    }
+                },
+                "r1": null,
+                "r2": null
+            },
+            "errors": [{
+                "type": "NOT_FOUND",
+                "message": "Could not resolve to a Repository with the name 'owner/gone'.",
+                "path": ["r1"]
+            }]
+        }));
+
+        assert_eq!(repository(&mut response, "r0").unwrap().total_count, 0);
+        let error = repository(&mut response, "r1").unwrap_err().to_string();
+        assert!(error.contains("owner/gone"), "{}", error);
+        // Another repository's error says nothing about this one
+        let error = repository(&mut response, "r2").unwrap_err().to_string();
+        assert_eq!(error, "repository missing from GraphQL response");
+    }
+}
@@ -4,4 +4,37 @@ pub struct PullRequestInfo {
     pub title: String,
     pub branch: String,
     pub is_draft: bool,
+    pub url: String,
+    /// `APPROVED`, `CHANGES_REQUESTED` or `REVIEW_REQUIRED`, only known to the GraphQL backend
+    pub review_decision: Option<String>,
+    /// Combined check state of the head commit like `SUCCESS`, GraphQL backend only
+    pub ci_status: Option<String>,
+}
+
+impl PullRequestInfo {
+    /// Review decision and CI status in words, like `approved, ci success`
+    pub fn status_summary(&self) -> Option<String> {
+        let words = |state: &str| state.to_lowercase().replace('_', " ");
+        let parts: Vec<String> = self
+            .review_decision
+            .as_deref()
+            .map(words)
+            .into_iter()
+            .chain(
+                self.ci_status
+                    .as_deref()
+                    .map(|ci| format!("ci {}", words(ci))),
+            )
+            .collect();
+
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// Open pull requests of a repository, at most the configured number of them
+#[derive(Debug, Clone, Default)]
+pub struct PullRequestListing {
+    pub prs: Vec<PullRequestInfo>,
+    /// Open pull requests in total, including the ones beyond the cap
+    pub total: usize,
 }
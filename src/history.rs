@@ -0,0 +1,471 @@
+use crate::repo::filters::RepoFilters;
+use crate::repo::findings::CheckKind;
+use crate::repo::scan::repo_key;
+use crate::repo::status::RepoStatus;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY,
+    started_at INTEGER NOT NULL,
+    root TEXT NOT NULL,
+    repo_count INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    scan_id INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+    repo TEXT NOT NULL,
+    check_id TEXT NOT NULL,
+    failing INTEGER NOT NULL,
+    detail TEXT
+);
+CREATE INDEX IF NOT EXISTS results_repo_check ON results(repo, check_id);
+CREATE TABLE IF NOT EXISTS pull_requests (
+    scan_id INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    branch TEXT NOT NULL,
+    is_draft INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS pull_requests_scan ON pull_requests(scan_id);
+";
+
+/// Scans are forgotten after this long unless configured otherwise, a year
+pub const DEFAULT_RETENTION: u64 = 52 * 7 * 24 * 60 * 60;
+
+/// How long a check has been in its current state for one repository
+#[derive(Debug, Clone)]
+pub struct CheckHistory {
+    pub check_id: String,
+    pub failing: bool,
+    pub detail: Option<String>,
+    /// Timestamp of the first scan of the current streak
+    pub since: i64,
+    /// Timestamp of the most recent scan
+    pub last_seen: i64,
+    /// Number of times the check flipped between passing and failing
+    pub transitions: usize,
+}
+
+/// What a repository looked like in a recorded scan
+#[derive(Debug, Clone, Default)]
+pub struct RepoSnapshot {
+    /// Checks that ran, with the finding detail if they failed
+    pub checks: HashMap<String, Option<String>>,
+    pub pr_numbers: HashSet<u64>,
+}
+
+/// Failing checks in the last scan of a week
+#[derive(Debug, Clone)]
+pub struct WeekTrend {
+    pub week: String,
+    pub counts: Vec<(String, u64)>,
+}
+
+impl WeekTrend {
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// Local SQLite database with the results of every scan
+pub struct HistoryDb {
+    conn: Connection,
+}
+
+/// Escape `value` for use as a literal in a `LIKE ... ESCAPE '\'` pattern
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl HistoryDb {
+    /// Default location under the XDG data directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("gitgud").join("history.db"))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Record the state of every enabled check for every scanned repository
+    ///
+    /// Acknowledged findings and fresh dirt are recorded as passing, the history
+    /// follows what the scan reported. Scans older than `retention` seconds are dropped along the way.
+    pub fn record_scan(
+        &mut self,
+        root: &Path,
+        results: &[(PathBuf, RepoStatus)],
+        filters: &RepoFilters,
+        retention: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let now = now_timestamp();
+        let tx = self.conn.transaction()?;
+
+        let cutoff = now.saturating_sub(i64::try_from(retention).unwrap_or(i64::MAX));
+        for table in ["results", "pull_requests"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE scan_id IN (SELECT id FROM scans WHERE started_at < ?1)",
+                    table
+                ),
+                params![cutoff],
+            )?;
+        }
+        tx.execute("DELETE FROM scans WHERE started_at < ?1", params![cutoff])?;
+
+        tx.execute(
+            "INSERT INTO scans (started_at, root, repo_count) VALUES (?1, ?2, ?3)",
+            params![now, repo_key(root), results.len() as i64],
+        )?;
+        let scan_id = tx.last_insert_rowid();
+
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO results (scan_id, repo, check_id, failing, detail)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (repo_path, status) in results {
+                let repo = repo_key(repo_path);
+                for check in CheckKind::ALL
+                    .iter()
+                    .filter(|check| check.is_enabled(filters))
+                {
+                    let finding = status.active_finding(*check, filters);
+                    insert.execute(params![
+                        scan_id,
+                        repo,
+                        check.id(),
+                        finding.is_some(),
+                        finding.map(|finding| finding.detail),
+                    ])?;
+                }
+            }
+
+            let mut insert_pr = tx.prepare(
+                "INSERT INTO pull_requests (scan_id, repo, number, title, branch, is_draft)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (repo_path, status) in results {
+                let repo = repo_key(repo_path);
+                for pr in &status.open_prs {
+                    insert_pr.execute(params![
+                        scan_id,
+                        repo,
+                        pr.number as i64,
+                        pr.title,
+                        pr.branch,
+                        pr.is_draft,
+                    ])?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Repositories as they were in the most recent scan of `root`
+    pub fn last_scan(
+        &self,
+        root: &Path,
+    ) -> Result<Option<HashMap<String, RepoSnapshot>>, Box<dyn std::error::Error>> {
+        let scan_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT MAX(id) FROM scans WHERE root = ?1",
+                params![repo_key(root)],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        let Some(scan_id) = scan_id else {
+            return Ok(None);
+        };
+
+        let mut snapshot: HashMap<String, RepoSnapshot> = HashMap::new();
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT repo, check_id, failing, detail FROM results WHERE scan_id = ?1")?;
+        let rows = stmt.query_map(params![scan_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (repo, check_id, failing, detail) = row?;
+            let detail = if failing {
+                detail.or(Some(String::new()))
+            } else {
+                None
+            };
+            snapshot
+                .entry(repo)
+                .or_default()
+                .checks
+                .insert(check_id, detail);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT repo, number FROM pull_requests WHERE scan_id = ?1")?;
+        let rows = stmt.query_map(params![scan_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (repo, number) = row?;
+            snapshot
+                .entry(repo)
+                .or_default()
+                .pr_numbers
+                .insert(number as u64);
+        }
+
+        Ok(Some(snapshot))
+    }
+
+    /// Resolve a repository given on the command line to the stored key
+    fn resolve_repo(&self, repo: &str) -> Result<Option<String>, rusqlite::Error> {
+        let path = Path::new(repo);
+        if path.exists() {
+            return Ok(Some(repo_key(path)));
+        }
+
+        // Fall back to matching on the directory name, `_` and `%` are no wildcards there
+        let mut stmt = self.conn.prepare(
+            "SELECT repo FROM results WHERE repo = ?1 OR repo LIKE '%/' || ?2 ESCAPE '\\'
+             GROUP BY repo ORDER BY MAX(scan_id) DESC LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![repo, escape_like(repo)])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Current streak of every check recorded for a repository
+    pub fn repo_history(
+        &self,
+        repo: &str,
+    ) -> Result<(String, Vec<CheckHistory>), Box<dyn std::error::Error>> {
+        let key = self
+            .resolve_repo(repo)?
+            .ok_or_else(|| format!("No scan history for {}", repo))?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT r.check_id, r.failing, r.detail, s.started_at
+             FROM results r JOIN scans s ON s.id = r.scan_id
+             WHERE r.repo = ?1
+             ORDER BY r.check_id, s.id DESC",
+        )?;
+        let rows = stmt.query_map(params![key], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, bool>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+
+        // Rows arrive newest first per check, the streak ends at the first state change
+        let mut history: Vec<CheckHistory> = Vec::new();
+        let mut streak_open = false;
+        let mut previous = false;
+        for row in rows {
+            let (check_id, failing, detail, started_at) = row?;
+
+            match history.last_mut() {
+                Some(current) if current.check_id == check_id => {
+                    if failing != previous {
+                        current.transitions += 1;
+                        streak_open = false;
+                    } else if streak_open {
+                        current.since = started_at;
+                    }
+                }
+                _ => {
+                    history.push(CheckHistory {
+                        check_id,
+                        failing,
+                        detail,
+                        since: started_at,
+                        last_seen: started_at,
+                        transitions: 0,
+                    });
+                    streak_open = true;
+                }
+            }
+            previous = failing;
+        }
+
+        Ok((key, history))
+    }
+
+    /// Failing check counts per week, taken from the last scan of each week and root
+    pub fn weekly_trend(&self, weeks: u32) -> Result<Vec<WeekTrend>, Box<dyn std::error::Error>> {
+        let cutoff = now_timestamp() - i64::from(weeks) * 7 * 24 * 60 * 60;
+        let mut stmt = self.conn.prepare(
+            "SELECT strftime('%Y-W%W', s.started_at, 'unixepoch', 'localtime') AS week,
+                    r.check_id, SUM(r.failing)
+             FROM results r JOIN scans s ON s.id = r.scan_id
+             WHERE s.id IN (
+                 SELECT MAX(id) FROM scans WHERE started_at >= ?1
+                 GROUP BY root, strftime('%Y-W%W', started_at, 'unixepoch', 'localtime')
+             )
+             GROUP BY week, r.check_id
+             ORDER BY week, r.check_id",
+        )?;
+        let rows = stmt.query_map(params![cutoff], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u64>(2)?,
+            ))
+        })?;
+
+        let mut trend: Vec<WeekTrend> = Vec::new();
+        for row in rows {
+            let (week, check_id, count) = row?;
+            match trend.last_mut() {
+                Some(current) if current.week == week => current.counts.push((check_id, count)),
+                _ => trend.push(WeekTrend {
+                    week,
+                    counts: vec![(check_id, count)],
+                }),
+            }
+        }
+
+        Ok(trend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::status::GitStatus;
+
+    fn temp_db(name: &str) -> (PathBuf, HistoryDb) {
+        let path =
+            std::env::temp_dir().join(format!("gitgud-history-{}-{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        let db = HistoryDb::open(&path).unwrap();
+        (path, db)
+    }
+
+    fn scan_count(db: &HistoryDb) -> i64 {
+        db.conn
+            .query_row("SELECT COUNT(*) FROM scans", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like("my_repo"), "my\\_repo");
+        assert_eq!(escape_like("100%\\x"), "100\\%\\\\x");
+    }
+
+    #[test]
+    fn resolves_names_literally() {
+        let (path, mut db) = temp_db("resolve");
+        let status = RepoStatus::new(GitStatus::new(), Vec::new(), HashSet::new());
+        let filters = RepoFilters::git_only(Default::default());
+        let results = vec![(PathBuf::from("/nowhere/myXrepo"), status)];
+        db.record_scan(Path::new("/nowhere"), &results, &filters, DEFAULT_RETENTION)
+            .unwrap();
+
+        assert_eq!(
+            db.resolve_repo("myXrepo").unwrap().as_deref(),
+            Some("/nowhere/myXrepo")
+        );
+        assert_eq!(db.resolve_repo("my_repo").unwrap(), None);
+        assert_eq!(db.resolve_repo("my%").unwrap(), None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn prunes_scans_past_retention() {
+        let (path, mut db) = temp_db("retention");
+        let filters = RepoFilters::git_only(Default::default());
+        db.record_scan(Path::new("/nowhere"), &[], &filters, DEFAULT_RETENTION)
+            .unwrap();
+        db.conn
+            .execute("UPDATE scans SET started_at = started_at - 1000", [])
+            .unwrap();
+
+        db.record_scan(Path::new("/nowhere"), &[], &filters, DEFAULT_RETENTION)
+            .unwrap();
+        assert_eq!(scan_count(&db), 2);
+
+        db.record_scan(Path::new("/nowhere"), &[], &filters, 500)
+            .unwrap();
+        assert_eq!(scan_count(&db), 2);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn records_acknowledged_findings_as_passing() {
+        let (path, mut db) = temp_db("active");
+        let mut git_status = GitStatus::new();
+        git_status.untracked_files = true;
+        git_status.unstaged_changes = true;
+        let mut status = RepoStatus::new(git_status, Vec::new(), HashSet::new());
+        let day = chrono::NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+        status.acknowledged.insert(
+            CheckKind::Untracked,
+            crate::ack::Acknowledgement {
+                repo: String::new(),
+                check: CheckKind::Untracked.id().to_string(),
+                until: day,
+                reason: String::new(),
+                created: day,
+            },
+        );
+
+        let filters = RepoFilters::git_only(Default::default());
+        let results = vec![(PathBuf::from("/nowhere/repo"), status)];
+        db.record_scan(Path::new("/nowhere"), &results, &filters, DEFAULT_RETENTION)
+            .unwrap();
+
+        let previous = db.last_scan(Path::new("/nowhere")).unwrap().unwrap();
+        let checks = &previous["/nowhere/repo"].checks;
+        assert_eq!(checks[CheckKind::Untracked.id()], None);
+        assert!(checks[CheckKind::Unstaged.id()].is_some());
+        let trend = &db.weekly_trend(1).unwrap()[0];
+        assert!(trend
+            .counts
+            .contains(&(CheckKind::Untracked.id().to_string(), 0)));
+        assert_eq!(trend.total(), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+}
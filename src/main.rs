@@ -1,29 +1,75 @@
+mod ack;
 mod args;
+mod backup;
 mod config;
 mod display;
+mod duplicates;
+mod fix;
 mod git;
 mod github;
+mod history;
+mod pull;
+mod relocate;
 mod repo;
+mod report;
+mod sync;
+mod template;
+mod tui;
+mod watch;
 
-use args::Args;
+use ack::AckStore;
+use args::{Args, ColorChoice, Command, OutputFormat};
 use clap::Parser;
 use colored::Colorize;
-use config::Config;
-use display::display_repos_status;
-use git::status::check_git_status;
-use github::api::fetch_github_prs;
+use config::{GithubBackend, RemoteNames};
+use display::{
+    configure_output, display_changes, display_repo_history, display_repos_status,
+    display_repos_template, display_trend,
+};
+use fix::FixMode;
+use github::client::GithubClient;
+use history::HistoryDb;
+use pull::{display_pull_summary, pull_repos};
+use repo::cache::ScanCache;
+use repo::diff::diff_scan;
+use repo::fetch::fetch_repos;
 use repo::filters::RepoFilters;
-use repo::status::RepoStatus;
-use std::collections::HashSet;
-use std::fs;
+use repo::health::check_remotes_health;
+use repo::layout::discover_nested_repos;
+use repo::scan::{discover_workspace, scan_repos, ScanOptions};
+use report::junit::render_junit;
+use report::porcelain::render_porcelain;
+use report::prometheus::{render_prometheus, write_textfile};
+use report::sarif::render_sarif;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use watch::WatchOptions;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command line arguments
     let args = Args::parse();
 
+    let format = if args.porcelain {
+        OutputFormat::Porcelain
+    } else {
+        args.format
+    };
+
+    // Quiet and porcelain output must not be polluted by colors or debug noise
+    let plain = args.quiet || format == OutputFormat::Porcelain;
+    let debug = args.debug && !plain;
+
+    // Decide on colors and hyperlinks before anything is printed
+    let color = if plain {
+        ColorChoice::Never
+    } else {
+        args.color
+    };
+    configure_output(color, args.hyperlinks);
+
     // Load config file if it exists
     // TODO: I hate this
     let config = config::load_config(args.config.as_deref())?;
@@ -50,10 +96,81 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // For include_draft_prs, we should only apply it if check_prs is true,
         // but still respect the command line value
         include_draft_prs: args.include_draft_prs,
+        dirty_warn_after: match args.dirty_warn_after {
+            Some(seconds) => Some(seconds),
+            None => config
+                .dirty_warn_after
+                .as_deref()
+                .map(config::parse_duration)
+                .transpose()?,
+        },
+        check_fetch: args.fetch,
+        check_layout: args.canonical_layout || config.canonical_layout,
+        check_fork: args.check_fork,
+        check_remote_health: args.check_remote_health || config.check_remote_health,
+        remotes: RemoteNames {
+            primary: args
+                .primary_remote
+                .unwrap_or(config.remotes.primary.clone()),
+            upstream: args
+                .upstream_remote
+                .unwrap_or(config.remotes.upstream.clone()),
+        },
+    };
+
+    // Parse the output template up front so mistakes are reported before scanning
+    let template = match args.template.as_ref().or(config.template.as_ref()) {
+        Some(spec) => Some(template::load_template(spec, display::TEMPLATE_FIELDS)?),
+        None => None,
     };
 
-    // Get GitHub token
+    // One GitHub client for the whole run, so every lookup shares the rate limit
     let github_token = args.github_token.or(config.github.token.clone());
+    let github = GithubClient::new(github_token.as_deref())?;
+
+    // GitHub only answers GraphQL queries that carry a token
+    let github_backend = args.github_backend.unwrap_or(config.github.backend);
+    if filters.check_prs && github_backend == GithubBackend::Graphql && github_token.is_none() {
+        return Err(
+            "The graphql backend needs a GitHub token, set GITHUB_TOKEN or use --github-backend rest"
+                .into(),
+        );
+    }
+
+    // Load the scan cache, --refresh starts from an empty one that still gets saved
+    let cache_path = ScanCache::default_path().filter(|_| !args.no_cache);
+    let cache = cache_path.as_ref().map(|path| {
+        let cache = if args.refresh {
+            ScanCache::default()
+        } else {
+            ScanCache::load(path)
+        };
+        Arc::new(Mutex::new(cache))
+    });
+
+    // Resolve and display target directory
+    let root = if args.directory.as_path() == Path::new(".") {
+        std::env::current_dir().unwrap()
+    } else {
+        args.directory.clone()
+    };
+
+    let options = ScanOptions {
+        filters: filters.clone(),
+        github,
+        max_prs: args
+            .max_prs
+            .or(config.github.max_prs)
+            .unwrap_or(config::DEFAULT_MAX_PRS),
+        github_backend,
+        protected_branches,
+        debug,
+        cache: cache.clone(),
+        acks: Arc::new(load_acks()),
+        layout_root: filters.check_layout.then(|| root.clone()),
+    };
+
+    let discover = |root: &Path| discover_workspace(root, &filters);
 
     // Configure thread pool using either command line or config value
     let threads = args.threads.or(config.threads);
@@ -64,103 +181,333 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .unwrap();
     }
 
-    // Resolve and display target directory
-    let root = if args.directory == std::path::PathBuf::from(".") {
-        std::env::current_dir().unwrap()
-    } else {
-        args.directory.clone()
-    };
+    match args.command {
+        Some(Command::Tui) => {
+            let repos = discover(&root)?;
+            let fetch_timeout = Duration::from_secs(args.fetch_timeout);
+            return tui::run_tui(root, repos, options, fetch_timeout).await;
+        }
+        Some(Command::Watch {
+            ndjson,
+            debounce_ms,
+        }) => {
+            let watch = WatchOptions {
+                ndjson,
+                debounce: Duration::from_millis(debounce_ms),
+                show_all: args.all,
+            };
+            return watch::run_watch(root, options, watch).await;
+        }
+        Some(Command::History { repo }) => {
+            let db = open_history_db()?;
+            let (key, history) = db.repo_history(&repo)?;
+            display_repo_history(&key, &history);
+            return Ok(());
+        }
+        Some(Command::Ack {
+            repo,
+            check,
+            until,
+            reason,
+        }) => {
+            if !repo.join(".git").exists() {
+                return Err(format!("{} is not a git repository", repo.display()).into());
+            }
+
+            let path = ack_store_path()?;
+            let mut store = AckStore::load(&path)?;
+            store.prune_expired();
+            store.acknowledge(&repo, check, until, reason);
+            store.save(&path)?;
+
+            println!(
+                "[?] Acknowledged {} for {} until {}",
+                check.id(),
+                repo.display(),
+                until
+            );
+            return Ok(());
+        }
+        Some(Command::Unack { repo, check }) => {
+            let path = ack_store_path()?;
+            let mut store = AckStore::load(&path)?;
+            if !store.remove(&repo, check) {
+                return Err(format!(
+                    "No acknowledgement of {} for {}",
+                    check.id(),
+                    repo.display()
+                )
+                .into());
+            }
+            store.save(&path)?;
+
+            println!(
+                "[?] Removed acknowledgement of {} for {}",
+                check.id(),
+                repo.display()
+            );
+            return Ok(());
+        }
+        Some(Command::Trend { weeks }) => {
+            let db = open_history_db()?;
+            display_trend(&db.weekly_trend(weeks)?);
+            return Ok(());
+        }
+        Some(Command::Fix {
+            dry_run: _,
+            apply,
+            interactive,
+        }) => {
+            let mode = if apply {
+                FixMode::Apply
+            } else if interactive {
+                FixMode::Interactive
+            } else {
+                FixMode::DryRun
+            };
+            return fix::run_fix(&discover(&root)?, &options, mode);
+        }
+        Some(Command::Backup { dest }) => {
+            backup::run_backup(&root, &discover(&root)?, &dest)?;
+            return Ok(());
+        }
+        Some(Command::Restore { backup, into }) => {
+            return backup::run_restore(&backup, into.as_deref());
+        }
+        Some(Command::Sync { manifest }) => {
+            let manifest_path = manifest
+                .or(config.manifest.clone())
+                .ok_or("No workspace manifest, set `manifest` in the config or pass --manifest")?;
+            let manifest = config::load_manifest(&manifest_path)?;
+            return sync::run_sync(&root, &discover(&root)?, &manifest, &filters.remotes, debug);
+        }
+        Some(Command::Relocate { apply }) => {
+            return relocate::run_relocate(
+                &root,
+                &discover_nested_repos(&root)?,
+                &filters.remotes,
+                apply,
+            );
+        }
+        Some(Command::Duplicates) => {
+            duplicates::run_duplicates(&discover(&root)?, &filters.remotes);
+            return Ok(());
+        }
+        Some(Command::Pull) => {
+            let repos = discover(&root)?;
+            println!(
+                "{}",
+                format!("[?] Pulling {} repositories", repos.len())
+                    .cyan()
+                    .bold()
+            );
+
+            let timeout = Duration::from_secs(args.fetch_timeout);
+            let results =
+                pull_repos(&repos, &filters.remotes, args.fetch_jobs, timeout, debug).await?;
+            display_pull_summary(&results);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // Only the text format gets banners, the others must stay machine readable
+    let text_output = format == OutputFormat::Text && template.is_none() && !args.quiet;
+
+    if text_output {
+        println!(
+            "{}",
+            format!("[?] Target directory: {}", root.display())
+                .cyan()
+                .bold()
+        );
+    }
 
-    println!(
-        "{}",
-        format!("[?] Target directory: {}", root.display())
-            .cyan()
-            .bold()
-    );
+    let scan_start = Instant::now();
 
-    // Get all entries in directory
-    let entries: Vec<_> = fs::read_dir(&root)?
-        .filter_map(Result::ok)
-        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
-        .filter(|entry| entry.path().join(".git").is_dir())
-        .collect();
+    // Get all repositories in directory
+    let repos = discover(&root)?;
 
     // Store the count before processing
-    let repo_count = entries.len();
+    let repo_count = repos.len();
 
-    if args.debug {
+    if debug {
         println!("[-] Found {} git repositories", repo_count);
-        println!("[-] Active filters: {:?}", filters);
+        println!("[-] Active filters: {:?}", options.filters);
     }
 
+    // Bring remote-tracking refs up to date so ahead checks aren't stale
+    let mut fetch_errors = if args.fetch {
+        if text_output {
+            println!(
+                "{}",
+                format!("[?] Fetching {} repositories", repo_count).cyan()
+            );
+        }
+        let timeout = Duration::from_secs(args.fetch_timeout);
+        fetch_repos(&repos, args.fetch_jobs, timeout, debug).await?
+    } else {
+        HashMap::new()
+    };
+
+    // Find remotes that stopped answering or moved elsewhere on GitHub
+    let mut remote_issues = if filters.check_remote_health {
+        let timeout = Duration::from_secs(args.fetch_timeout);
+        check_remotes_health(&repos, args.fetch_jobs, timeout, &options.github, debug).await?
+    } else {
+        HashMap::new()
+    };
+
     // Process repositories in parallel
-    let results = Arc::new(Mutex::new(Vec::new()));
-
-    // We use a specific collection for handles to avoid consuming 'entries'
-    let handles: Vec<_> = entries
-        .iter() // Use iter() instead of into_iter() to keep ownership
-        .map(|entry| {
-            let repo_path = entry.path();
-            let filters = filters.clone();
-            let results = Arc::clone(&results);
-            let token = github_token.clone();
-            let protected_branches = protected_branches.clone();
-            let debug = args.debug;
-
-            tokio::spawn(async move {
-                if debug {
-                    println!("[-] Checking repository {}", repo_path.display());
-                }
+    let mut results = scan_repos(&repos, &options).await?;
+    for (repo_path, status) in &mut results {
+        status.fetch_error = fetch_errors.remove(repo_path);
+        status.remote_issues = remote_issues.remove(repo_path).unwrap_or_default();
+    }
 
-                // Get git status
-                let git_status = check_git_status(&repo_path, &filters, debug);
-
-                // Get GitHub PR info if needed
-                let mut prs = Vec::new();
-                if filters.check_prs {
-                    if let Ok(repo_prs) =
-                        fetch_github_prs(&repo_path, token.as_deref(), debug).await
-                    {
-                        if debug {
-                            println!(
-                                "[-] Found {} open PRs for {}",
-                                repo_prs.len(),
-                                repo_path.display()
-                            );
-                        }
-
-                        // Filter draft PRs if needed
-                        prs = if filters.include_draft_prs {
-                            repo_prs
-                        } else {
-                            repo_prs.into_iter().filter(|pr| !pr.is_draft).collect()
-                        };
-                    } else if debug {
-                        println!("[-] Failed to fetch PRs for {}", repo_path.display());
-                    }
-                }
+    let rate_limit = options.github.rate_limit();
+    if debug && rate_limit.requests > 0 {
+        println!("[-] {}", rate_limit.summary());
+    }
+
+    let rate_limited = results
+        .iter()
+        .filter(|(_, status)| status.rate_limited)
+        .count();
+    if text_output && rate_limited > 0 {
+        println!(
+            "{}",
+            format!("[!] GitHub rate limited, {} repos skipped", rate_limited)
+                .yellow()
+                .bold()
+        );
+    }
+
+    let scan_duration = scan_start.elapsed();
 
-                // Combine into repo status
-                let repo_status = RepoStatus::new(git_status, prs, protected_branches);
+    if let (Some(path), Some(cache)) = (&cache_path, &cache) {
+        if let Err(e) = cache.lock().unwrap().save(path) {
+            if debug {
+                println!("[-] Unable to save scan cache {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    // Read the previous scan before this one is recorded
+    let previous_scan = if args.since_last {
+        open_history_db()?.last_scan(&root)?
+    } else {
+        None
+    };
 
-                let mut results_guard = results.lock().unwrap();
-                if args.all || repo_status.has_issues(&filters) {
-                    results_guard.push((repo_path.clone(), repo_status));
+    // Exporters run from cron would fill the database with every run
+    let record_history = !args.no_history
+        && (args.record_history
+            || args.since_last
+            || (text_output && args.write_textfile.is_none()));
+    if record_history {
+        let retention = match config.history_retention.as_deref() {
+            Some(retention) => config::parse_duration(retention)?,
+            None => history::DEFAULT_RETENTION,
+        };
+        let recorded = open_history_db()
+            .and_then(|mut db| db.record_scan(&root, &results, &filters, retention));
+        if let Err(e) = recorded {
+            if debug {
+                println!("[-] Unable to record scan history: {}", e);
+            }
+        }
+    }
+
+    if let Some(path) = &args.write_textfile {
+        let metrics = render_prometheus(&results, &filters, repo_count, scan_duration);
+        write_textfile(path, &metrics)?;
+
+        if debug {
+            println!("[-] Wrote Prometheus metrics to {}", path.display());
+        }
+    }
+
+    if args.quiet {
+        let found_issues = results
+            .iter()
+            .any(|(_, status)| status.has_issues(&filters));
+        std::process::exit(i32::from(found_issues));
+    }
+
+    if args.since_last {
+        match &previous_scan {
+            Some(previous) => {
+                let changes = diff_scan(&results, previous, &filters);
+                if text_output {
+                    display_changes(&changes);
+                    println!("Scan complete: {} repositories processed", repo_count);
+                    return Ok(());
                 }
-            })
-        })
-        .collect();
 
-    // Wait for all tasks to complete
-    for handle in handles {
-        handle.await?;
+                // Machine readable formats report the repositories that changed
+                results.retain(|(repo_path, _)| {
+                    changes.iter().any(|(changed, _)| changed == repo_path)
+                });
+            }
+            None if text_output => {
+                println!(
+                    "{}",
+                    "[?] No previous scan recorded, showing everything".cyan()
+                );
+            }
+            None => {}
+        }
     }
 
-    // Display results
-    let results_guard = results.lock().unwrap();
-    display_repos_status(&results_guard, &filters);
+    // JUnit and Prometheus report passing checks too, so keep clean repositories around
+    let keep_all = args.all || matches!(format, OutputFormat::Junit | OutputFormat::Prometheus);
+    if !keep_all {
+        results.retain(|(_, status)| status.has_issues(&filters));
+    }
 
-    println!("Scan complete: {} repositories processed", repo_count);
+    match format {
+        OutputFormat::Text => match &template {
+            Some(template) => display_repos_template(&results, &filters, template),
+            None => {
+                display_repos_status(&results, &filters);
+                println!("Scan complete: {} repositories processed", repo_count);
+            }
+        },
+        OutputFormat::Junit => print!("{}", render_junit(&results, &filters)),
+        OutputFormat::Sarif => println!("{}", render_sarif(&results, &filters)),
+        OutputFormat::Prometheus => print!(
+            "{}",
+            render_prometheus(&results, &filters, repo_count, scan_duration)
+        ),
+        OutputFormat::Porcelain => print!("{}", render_porcelain(&results, &filters)),
+    }
 
     Ok(())
 }
+
+fn ack_store_path() -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    Ok(AckStore::default_path().ok_or("Unable to determine data directory")?)
+}
+
+/// Acknowledgements for a scan, a broken state file only costs the acknowledgements
+fn load_acks() -> AckStore {
+    let loaded = ack_store_path().and_then(|path| {
+        AckStore::load(&path).map_err(|e| format!("{}: {}", path.display(), e).into())
+    });
+
+    loaded.unwrap_or_else(|e| {
+        eprintln!(
+            "{}",
+            format!("[!] Ignoring acknowledgements, unable to read {}", e)
+                .yellow()
+                .bold()
+        );
+        AckStore::default()
+    })
+}
+
+fn open_history_db() -> Result<HistoryDb, Box<dyn std::error::Error>> {
+    let path = HistoryDb::default_path().ok_or("Unable to determine data directory")?;
+    HistoryDb::open(&path)
+}
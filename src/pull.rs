@@ -0,0 +1,142 @@
+use crate::config::RemoteNames;
+use crate::display::path_link;
+use crate::git::branches::{fast_forward, is_worktree_clean, upstream_divergence};
+use crate::git::remote::fetch_remotes_unattended;
+use crate::git::status::check_git_status;
+use crate::repo::filters::RepoFilters;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// What `gitgud pull` did with a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullOutcome {
+    Updated(u32),
+    UpToDate,
+    Skipped(String),
+    Failed(String),
+}
+
+/// Fetch and fast-forward one repository, if it is clean and on its default branch
+async fn pull_repo(
+    repo_path: &Path,
+    remotes: &RemoteNames,
+    timeout: Duration,
+    debug: bool,
+) -> PullOutcome {
+    // Look at everything that could make an update unsafe, whatever checks are enabled
+    let filters = RepoFilters::git_only(remotes.clone());
+    let status = check_git_status(repo_path, &filters, debug);
+
+    let skip_reason = if status.untracked_files {
+        Some("untracked files".to_string())
+    } else if !is_worktree_clean(repo_path) {
+        // Staged additions, renames and copies count too, not just what the unstaged check flags
+        Some("uncommitted changes".to_string())
+    } else if status.ahead_of_remote {
+        Some("branch ahead of remote".to_string())
+    } else if status.missing_remote {
+        Some("no remotes".to_string())
+    } else if !status.is_default_branch {
+        Some(format!("on branch {}", status.current_branch))
+    } else {
+        None
+    };
+    if let Some(reason) = skip_reason {
+        return PullOutcome::Skipped(reason);
+    }
+
+    if let Err(e) = fetch_remotes_unattended(repo_path, timeout).await {
+        return PullOutcome::Failed(format!("fetch failed: {}", e));
+    }
+
+    match upstream_divergence(repo_path) {
+        None => PullOutcome::Skipped("no upstream branch".to_string()),
+        Some((0, 0)) => PullOutcome::UpToDate,
+        Some((0, behind)) => match fast_forward(repo_path) {
+            Ok(()) => PullOutcome::Updated(behind),
+            Err(e) => PullOutcome::Failed(e),
+        },
+        Some((_, _)) => PullOutcome::Skipped("diverged from upstream, needs a merge".to_string()),
+    }
+}
+
+/// Fast-forward every clean repository on its default branch, at most `jobs` at a time
+pub async fn pull_repos(
+    repos: &[PathBuf],
+    remotes: &RemoteNames,
+    jobs: usize,
+    timeout: Duration,
+    debug: bool,
+) -> Result<Vec<(PathBuf, PullOutcome)>, tokio::task::JoinError> {
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = repos
+        .iter()
+        .map(|repo_path| {
+            let repo_path = repo_path.clone();
+            let permits = Arc::clone(&permits);
+            let results = Arc::clone(&results);
+            let remotes = remotes.clone();
+
+            tokio::spawn(async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+                let outcome = pull_repo(&repo_path, &remotes, timeout, debug).await;
+                results.lock().unwrap().push((repo_path, outcome));
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await?;
+    }
+
+    let mut results = std::mem::take(&mut *results.lock().unwrap());
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(results)
+}
+
+pub fn display_pull_summary(results: &[(PathBuf, PullOutcome)]) {
+    let (mut updated, mut up_to_date, mut skipped, mut failed) = (0, 0, 0, 0);
+
+    for (repo_path, outcome) in results {
+        let path = path_link(repo_path);
+
+        match outcome {
+            PullOutcome::Updated(count) => {
+                updated += 1;
+                let commits = if *count == 1 { "commit" } else { "commits" };
+                println!(
+                    "{}",
+                    format!("[+] {} => pulled {} {}", path, count, commits)
+                        .green()
+                        .bold()
+                );
+            }
+            PullOutcome::UpToDate => {
+                up_to_date += 1;
+                println!("{}", format!("[=] {} => already up to date", path).dimmed());
+            }
+            PullOutcome::Skipped(reason) => {
+                skipped += 1;
+                println!(
+                    "{}",
+                    format!("[-] {} => skipped: {}", path, reason).yellow()
+                );
+            }
+            PullOutcome::Failed(e) => {
+                failed += 1;
+                println!("{}", format!("[!] {} => failed: {}", path, e).red().bold());
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Pull complete: {} updated, {} up to date, {} skipped, {} failed",
+        updated, up_to_date, skipped, failed
+    );
+}
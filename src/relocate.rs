@@ -0,0 +1,153 @@
+use crate::config::RemoteNames;
+use crate::display::path_link;
+use crate::fix::shell_quote;
+use crate::repo::layout::misplaced;
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Linked worktrees, they point at the repository by absolute path and break when it moves
+fn linked_worktrees(repo_path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(repo_path.join(".git").join("worktrees")) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| fs::read_to_string(entry.path().join("gitdir")).ok())
+        .filter_map(|gitdir| Path::new(gitdir.trim()).parent().map(Path::to_path_buf))
+        .collect()
+}
+
+/// Where a worktree ends up, ones inside the repository move along with it
+fn moved_worktree(worktree: &Path, from: &Path, to: &Path) -> PathBuf {
+    match worktree.strip_prefix(from) {
+        Ok(relative) => to.join(relative),
+        Err(_) => worktree.to_path_buf(),
+    }
+}
+
+fn relocate_repo(from: &Path, to: &Path, worktrees: &[PathBuf]) -> Result<(), String> {
+    if to.exists() {
+        return Err(format!("{} already exists", to.display()));
+    }
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::rename(from, to).map_err(|e| e.to_string())?;
+
+    if !worktrees.is_empty() {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(to)
+            .arg("worktree")
+            .arg("repair")
+            .args(worktrees)
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "moved, but repairing worktrees failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Move every repository that is not at `<root>/<host>/<owner>/<repo>` to where it belongs
+///
+/// Without `apply` only the moves are printed.
+pub fn run_relocate(
+    root: &Path,
+    repos: &[PathBuf],
+    remotes: &RemoteNames,
+    apply: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut planned, mut failed) = (0, 0);
+    let root = &fs::canonicalize(root)?;
+
+    for repo_path in repos {
+        let Some(target) = misplaced(root, repo_path, remotes) else {
+            continue;
+        };
+        planned += 1;
+
+        let path = path_link(repo_path);
+        if !target.starts_with(root) || target == *root {
+            println!(
+                "{}",
+                format!(
+                    "[!] {} => {} is outside {}",
+                    path,
+                    target.display(),
+                    root.display()
+                )
+                .red()
+                .bold()
+            );
+            failed += 1;
+            continue;
+        }
+        println!(
+            "{}",
+            format!("[>] {} => {}", path, target.display())
+                .magenta()
+                .bold()
+        );
+
+        let from = fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.clone());
+        let worktrees: Vec<PathBuf> = linked_worktrees(repo_path)
+            .iter()
+            .map(|worktree| moved_worktree(worktree, &from, &target))
+            .collect();
+
+        println!(
+            "  mv {} {}",
+            shell_quote(&repo_path.display().to_string()),
+            shell_quote(&target.display().to_string())
+        );
+        if !worktrees.is_empty() {
+            let mut command = format!(
+                "git -C {} worktree repair",
+                shell_quote(&target.display().to_string())
+            );
+            for worktree in &worktrees {
+                command.push(' ');
+                command.push_str(&shell_quote(&worktree.display().to_string()));
+            }
+            println!("  {}", command);
+        }
+
+        if apply {
+            if let Err(e) = relocate_repo(repo_path, &target, &worktrees) {
+                println!("{}", format!("[!] {} failed: {}", path, e).red().bold());
+                failed += 1;
+            }
+        }
+    }
+
+    println!();
+    if planned == 0 {
+        println!(
+            "{}",
+            "[=] Every repository is in its canonical location"
+                .green()
+                .bold()
+        );
+    } else if !apply {
+        println!(
+            "{}",
+            "[?] Dry run, re-run with --apply to move these repositories".cyan()
+        );
+    }
+
+    if failed > 0 {
+        return Err(format!("{} repositories could not be relocated", failed).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use crate::git::status::GitStatus;
+use crate::repo::filters::RepoFilters;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+/// Cheap to compute summary of everything `git status` looks at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    head: String,
+    index_mtime: u64,
+    config_mtime: u64,
+    refs_mtime: u64,
+    /// `.git/info/exclude` and the global excludes file
+    excludes_mtime: u64,
+    worktree_mtime: u64,
+    worktree_entries: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    filters: RepoFilters,
+    status: GitStatus,
+    /// Ignored directories as of the last scan, left out of the fingerprint
+    #[serde(default)]
+    ignored_dirs: Vec<PathBuf>,
+}
+
+/// Last known `GitStatus` of each repository, persisted between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    entries: HashMap<PathBuf, CacheEntry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+fn mtime(path: &Path) -> u64 {
+    fs::symlink_metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Newest mtime and number of entries below `dir`, skipping `.git` and `skip`
+fn walk_mtimes(dir: &Path, skip: &[PathBuf], newest: &mut u64, entries: &mut u64) {
+    *newest = (*newest).max(mtime(dir));
+
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.filter_map(Result::ok) {
+        let path = entry.path();
+        if entry.file_name() == ".git" || skip.contains(&path) {
+            continue;
+        }
+        *entries += 1;
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => walk_mtimes(&path, skip, newest, entries),
+            _ => *newest = (*newest).max(mtime(&path)),
+        }
+    }
+}
+
+/// The global excludes file, `core.excludesFile` or git's XDG default
+fn global_excludes_file() -> Option<&'static Path> {
+    static PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    PATH.get_or_init(|| {
+        let configured = Command::new("git")
+            .args(["config", "--global", "--path", "--get", "core.excludesFile"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+
+        configured.or_else(|| {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+                .map(|dir| dir.join("git").join("ignore"))
+        })
+    })
+    .as_deref()
+}
+
+/// Directories git ignores in the worktree, like `target/` or `node_modules/`
+///
+/// Their contents can't change the status, so the fingerprint doesn't walk them.
+pub fn ignored_dirs(repo_path: &Path) -> Vec<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args([
+            "ls-files",
+            "--others",
+            "--ignored",
+            "--exclude-standard",
+            "--directory",
+            "-z",
+        ])
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter_map(|entry| {
+                String::from_utf8_lossy(entry)
+                    .strip_suffix('/')
+                    .map(str::to_string)
+            })
+            .map(|dir| repo_path.join(dir))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Fingerprint {
+    /// Fingerprint of everything but the `ignored` directories of the worktree
+    pub fn compute(repo_path: &Path, ignored: &[PathBuf]) -> Option<Self> {
+        let git_dir = repo_path.join(".git");
+        let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+
+        let mut refs_mtime = mtime(&git_dir.join("packed-refs"));
+        let mut refs_entries = 0;
+        walk_mtimes(
+            &git_dir.join("refs"),
+            &[],
+            &mut refs_mtime,
+            &mut refs_entries,
+        );
+
+        let excludes_mtime = global_excludes_file()
+            .map(mtime)
+            .unwrap_or_default()
+            .max(mtime(&git_dir.join("info").join("exclude")));
+
+        let mut worktree_mtime = 0;
+        let mut worktree_entries = 0;
+        walk_mtimes(
+            repo_path,
+            ignored,
+            &mut worktree_mtime,
+            &mut worktree_entries,
+        );
+
+        Some(Self {
+            head: head.trim().to_string(),
+            index_mtime: mtime(&git_dir.join("index")),
+            config_mtime: mtime(&git_dir.join("config")),
+            refs_mtime,
+            excludes_mtime,
+            worktree_mtime,
+            worktree_entries: worktree_entries + refs_entries,
+        })
+    }
+}
+
+impl ScanCache {
+    /// Default location under the XDG cache directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("gitgud").join("scan-cache.json"))
+    }
+
+    /// Load the cache, starting empty if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write next to the target and rename so concurrent runs never see half a file
+        let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+
+    /// Ignored directories recorded with the last status of a repository
+    pub fn ignored_dirs(&self, repo_path: &Path) -> Vec<PathBuf> {
+        self.entries
+            .get(repo_path)
+            .map(|entry| entry.ignored_dirs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Cached status if the repository looks exactly like it did last time
+    pub fn lookup(
+        &self,
+        repo_path: &Path,
+        fingerprint: &Fingerprint,
+        filters: &RepoFilters,
+    ) -> Option<GitStatus> {
+        let entry = self.entries.get(repo_path)?;
+        if entry.fingerprint == *fingerprint && entry.filters == *filters {
+            Some(entry.status.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn store(
+        &mut self,
+        repo_path: &Path,
+        fingerprint: Fingerprint,
+        ignored_dirs: Vec<PathBuf>,
+        filters: &RepoFilters,
+        status: &GitStatus,
+    ) {
+        self.entries.insert(
+            repo_path.to_path_buf(),
+            CacheEntry {
+                fingerprint,
+                filters: filters.clone(),
+                status: status.clone(),
+                ignored_dirs,
+            },
+        );
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn fingerprint_skips_ignored_dirs_but_not_excludes() {
+        let repo = std::env::temp_dir().join(format!("gitgud-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        fs::create_dir_all(repo.join("target")).unwrap();
+        git(&repo, &["init", "--quiet"]);
+        fs::write(repo.join(".git/info/exclude"), "target/\n").unwrap();
+
+        fs::write(repo.join("target/build.o"), "x").unwrap();
+        let ignored = ignored_dirs(&repo);
+        assert_eq!(ignored, vec![repo.join("target")]);
+
+        let before = Fingerprint::compute(&repo, &ignored).unwrap();
+        fs::create_dir_all(repo.join("target/debug")).unwrap();
+        fs::write(repo.join("target/debug/deps.o"), "x").unwrap();
+        assert_eq!(Fingerprint::compute(&repo, &ignored).unwrap(), before);
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        fs::write(repo.join(".git/info/exclude"), "").unwrap();
+        assert_ne!(Fingerprint::compute(&repo, &ignored).unwrap(), before);
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
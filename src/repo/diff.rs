@@ -0,0 +1,223 @@
+use crate::github::pr::PullRequestInfo;
+use crate::history::RepoSnapshot;
+use crate::repo::filters::RepoFilters;
+use crate::repo::findings::{CheckKind, Finding};
+use crate::repo::scan::repo_key;
+use crate::repo::status::RepoStatus;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// What changed for a repository since the previous scan
+#[derive(Debug, Clone, Default)]
+pub struct RepoChanges {
+    pub new_findings: Vec<Finding>,
+    pub resolved: Vec<CheckKind>,
+    /// Something was resolved and no issues are left
+    pub now_clean: bool,
+    pub new_prs: Vec<PullRequestInfo>,
+}
+
+impl RepoChanges {
+    pub fn is_empty(&self) -> bool {
+        self.new_findings.is_empty() && self.resolved.is_empty() && self.new_prs.is_empty()
+    }
+}
+
+/// Compare scan results against the previous scan, keeping only repositories that changed
+///
+/// The history records active findings, so an ack running out or dirt passing
+/// `dirty_warn_after` shows up as new.
+pub fn diff_scan(
+    results: &[(PathBuf, RepoStatus)],
+    previous: &HashMap<String, RepoSnapshot>,
+    filters: &RepoFilters,
+) -> Vec<(PathBuf, RepoChanges)> {
+    let empty = RepoSnapshot::default();
+    let mut changed = Vec::new();
+
+    for (repo_path, status) in results {
+        let before = previous.get(&repo_key(repo_path)).unwrap_or(&empty);
+        let mut changes = RepoChanges::default();
+
+        for check in CheckKind::ALL
+            .iter()
+            .filter(|check| check.is_enabled(filters))
+        {
+            let was_failing = before.checks.get(check.id()).cloned().flatten();
+            // Acknowledged findings and fresh dirt are neither new nor resolved
+            let finding = status.active_finding(*check, filters);
+            let suppressed = finding.is_none() && status.finding(*check).is_some();
+
+            match (finding, was_failing) {
+                // Open PRs are compared by number below, the count alone says little
+                (Some(_), _) if *check == CheckKind::OpenPrs => {}
+                (Some(finding), Some(detail)) if finding.detail == detail => {}
+                (Some(finding), _) => changes.new_findings.push(finding),
+                (None, Some(_)) if !suppressed => changes.resolved.push(*check),
+                (None, _) => {}
+            }
+        }
+
+        if filters.check_prs {
+            changes.new_prs = status
+                .open_prs
+                .iter()
+                .filter(|pr| !before.pr_numbers.contains(&pr.number))
+                .cloned()
+                .collect();
+        }
+
+        changes.now_clean = !changes.resolved.is_empty() && !status.has_issues(filters);
+
+        if !changes.is_empty() {
+            changed.push((repo_path.clone(), changes));
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ack::{AckStore, Acknowledgement};
+    use crate::git::status::GitStatus;
+    use crate::history::{HistoryDb, DEFAULT_RETENTION};
+    use chrono::NaiveDate;
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    fn status(untracked: bool, unstaged: bool) -> RepoStatus {
+        let mut git_status = GitStatus::new();
+        git_status.untracked_files = untracked;
+        git_status.unstaged_changes = unstaged;
+        RepoStatus::new(git_status, Vec::new(), HashSet::new())
+    }
+
+    fn previous(repo_path: &Path, failing: &[(CheckKind, &str)]) -> HashMap<String, RepoSnapshot> {
+        let snapshot = RepoSnapshot {
+            checks: failing
+                .iter()
+                .map(|(check, detail)| (check.id().to_string(), Some(detail.to_string())))
+                .collect(),
+            pr_numbers: HashSet::new(),
+        };
+        HashMap::from([(repo_key(repo_path), snapshot)])
+    }
+
+    fn acknowledge(status: &mut RepoStatus, check: CheckKind) {
+        let day = NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+        status.acknowledged.insert(
+            check,
+            Acknowledgement {
+                repo: String::new(),
+                check: check.id().to_string(),
+                until: day,
+                reason: String::new(),
+                created: day,
+            },
+        );
+    }
+
+    #[test]
+    fn reports_new_and_resolved_findings() {
+        let filters = RepoFilters::git_only(Default::default());
+        let repo_path = PathBuf::from("/src/gitgud");
+        let previous = previous(
+            &repo_path,
+            &[(CheckKind::Unstaged, "changes not staged for commit")],
+        );
+
+        let changes = diff_scan(
+            &[(repo_path.clone(), status(true, false))],
+            &previous,
+            &filters,
+        );
+        assert_eq!(changes.len(), 1);
+        let (_, changes) = &changes[0];
+        assert_eq!(changes.new_findings.len(), 1);
+        assert_eq!(changes.new_findings[0].check, CheckKind::Untracked);
+        assert_eq!(changes.resolved, vec![CheckKind::Unstaged]);
+        assert!(!changes.now_clean);
+
+        let changes = diff_scan(&[(repo_path, status(false, false))], &previous, &filters);
+        assert_eq!(changes[0].1.resolved, vec![CheckKind::Unstaged]);
+        assert!(changes[0].1.now_clean);
+    }
+
+    #[test]
+    fn acknowledged_findings_are_neither_new_nor_resolved() {
+        let filters = RepoFilters::git_only(Default::default());
+        let repo_path = PathBuf::from("/src/gitgud");
+
+        let mut acked = status(true, false);
+        acknowledge(&mut acked, CheckKind::Untracked);
+        let results = [(repo_path.clone(), acked)];
+
+        let was_failing = previous(
+            &repo_path,
+            &[(CheckKind::Untracked, "untracked files found")],
+        );
+        assert!(diff_scan(&results, &was_failing, &filters).is_empty());
+        assert!(diff_scan(&results, &previous(&repo_path, &[]), &filters).is_empty());
+    }
+
+    #[test]
+    fn fresh_dirt_is_not_new() {
+        let mut filters = RepoFilters::git_only(Default::default());
+        filters.dirty_warn_after = Some(3600);
+        let repo_path = PathBuf::from("/src/gitgud");
+
+        let mut fresh = status(false, true);
+        fresh.dirty_oldest_mtime = Some(chrono::Utc::now().timestamp());
+
+        let changes = diff_scan(
+            &[(repo_path.clone(), fresh)],
+            &previous(&repo_path, &[]),
+            &filters,
+        );
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn expired_acks_and_aging_dirt_are_new_against_recorded_scans() {
+        let db_path = std::env::temp_dir().join(format!("gitgud-diff-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&db_path);
+        let mut db = HistoryDb::open(&db_path).unwrap();
+        let root = Path::new("/src");
+        let repo_path = PathBuf::from("/src/gitgud");
+        let mut filters = RepoFilters::git_only(Default::default());
+        filters.dirty_warn_after = Some(3600);
+        let now = chrono::Utc::now().timestamp();
+        let today = chrono::Local::now().date_naive();
+
+        let scan = |ack_until: NaiveDate, dirty_since: i64| {
+            let mut acks = AckStore::default();
+            acks.acknowledge(&repo_path, CheckKind::Untracked, ack_until, String::new());
+            let mut status = status(true, true);
+            status.dirty_oldest_mtime = Some(dirty_since);
+            status.acknowledged = acks.active_for(&repo_path);
+            vec![(repo_path.clone(), status)]
+        };
+
+        // Acknowledged and fresh: nothing to report, nothing recorded as failing
+        let first = scan(today + chrono::Days::new(1), now);
+        db.record_scan(root, &first, &filters, DEFAULT_RETENTION)
+            .unwrap();
+
+        // The ack ran out and the dirt got old in between
+        let second = scan(today - chrono::Days::new(1), now - 2 * 3600);
+        let previous = db.last_scan(root).unwrap().unwrap();
+        let changes = diff_scan(&second, &previous, &filters);
+        assert_eq!(changes.len(), 1);
+        let checks: Vec<CheckKind> = changes[0]
+            .1
+            .new_findings
+            .iter()
+            .map(|finding| finding.check)
+            .collect();
+        assert_eq!(checks, vec![CheckKind::Untracked, CheckKind::Unstaged]);
+
+        std::fs::remove_file(db_path).unwrap();
+    }
+}
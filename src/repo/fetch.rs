@@ -0,0 +1,50 @@
+use crate::git::remote::fetch_remotes_unattended;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Fetch all repositories in parallel, at most `jobs` at a time
+///
+/// Returns the error message for every repository whose fetch failed.
+pub async fn fetch_repos(
+    repos: &[PathBuf],
+    jobs: usize,
+    timeout: Duration,
+    debug: bool,
+) -> Result<HashMap<PathBuf, String>, tokio::task::JoinError> {
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let failures = Arc::new(Mutex::new(HashMap::new()));
+
+    let handles: Vec<_> = repos
+        .iter()
+        .map(|repo_path| {
+            let repo_path = repo_path.clone();
+            let permits = Arc::clone(&permits);
+            let failures = Arc::clone(&failures);
+
+            tokio::spawn(async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+
+                if debug {
+                    println!("[-] Fetching {}", repo_path.display());
+                }
+
+                if let Err(e) = fetch_remotes_unattended(&repo_path, timeout).await {
+                    if debug {
+                        println!("[-] Fetch failed for {}: {}", repo_path.display(), e);
+                    }
+                    failures.lock().unwrap().insert(repo_path, e);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await?;
+    }
+
+    let failures = std::mem::take(&mut *failures.lock().unwrap());
+    Ok(failures)
+}
@@ -1,4 +1,7 @@
-#[derive(Debug, Clone)]
+use crate::config::RemoteNames;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RepoFilters {
     pub check_untracked: bool,
     pub check_unstaged: bool,
@@ -7,4 +10,42 @@ pub struct RepoFilters {
     pub check_branch: bool,
     pub check_prs: bool,
     pub include_draft_prs: bool,
+    /// Only flag untracked and unstaged changes left untouched for this many seconds
+    pub dirty_warn_after: Option<u64>,
+    /// Remotes were fetched before checking, so fetch failures are findings
+    #[serde(default)]
+    pub check_fetch: bool,
+    /// Flag repositories outside of `<root>/<host>/<owner>/<repo>`
+    #[serde(default)]
+    pub check_layout: bool,
+    /// Compare forks with their upstream repository
+    #[serde(default)]
+    pub check_fork: bool,
+    /// Flag unreachable, moved, archived and deleted remotes
+    #[serde(default)]
+    pub check_remote_health: bool,
+    /// Which remotes ahead checks and PR lookups run against
+    #[serde(default)]
+    pub remotes: RemoteNames,
+}
+
+impl RepoFilters {
+    /// Every check that only looks at the local repository, nothing that needs the network
+    pub fn git_only(remotes: RemoteNames) -> Self {
+        Self {
+            check_untracked: true,
+            check_unstaged: true,
+            check_ahead: true,
+            check_no_remotes: true,
+            check_branch: true,
+            check_prs: false,
+            include_draft_prs: false,
+            dirty_warn_after: None,
+            check_fetch: false,
+            check_layout: false,
+            check_fork: false,
+            check_remote_health: false,
+            remotes,
+        }
+    }
 }
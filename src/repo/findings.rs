@@ -0,0 +1,262 @@
+use crate::repo::filters::RepoFilters;
+use crate::repo::status::RepoStatus;
+use std::str::FromStr;
+
+/// The individual checks gitgud runs against a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckKind {
+    Untracked,
+    Unstaged,
+    Ahead,
+    NoRemote,
+    Branch,
+    OpenPrs,
+    FetchFailed,
+    Misplaced,
+    BehindUpstream,
+    MergedUpstream,
+    UpstreamPrs,
+    RemoteHealth,
+    RateLimited,
+}
+
+impl CheckKind {
+    pub const ALL: [CheckKind; 13] = [
+        CheckKind::Untracked,
+        CheckKind::Unstaged,
+        CheckKind::Ahead,
+        CheckKind::NoRemote,
+        CheckKind::Branch,
+        CheckKind::OpenPrs,
+        CheckKind::FetchFailed,
+        CheckKind::Misplaced,
+        CheckKind::BehindUpstream,
+        CheckKind::MergedUpstream,
+        CheckKind::UpstreamPrs,
+        CheckKind::RemoteHealth,
+        CheckKind::RateLimited,
+    ];
+
+    /// Stable identifier used in machine readable output
+    pub fn id(&self) -> &'static str {
+        match self {
+            CheckKind::Untracked => "untracked",
+            CheckKind::Unstaged => "unstaged",
+            CheckKind::Ahead => "ahead",
+            CheckKind::NoRemote => "no-remote",
+            CheckKind::Branch => "branch",
+            CheckKind::OpenPrs => "open-prs",
+            CheckKind::FetchFailed => "fetch-failed",
+            CheckKind::Misplaced => "misplaced",
+            CheckKind::BehindUpstream => "behind-upstream",
+            CheckKind::MergedUpstream => "merged-upstream",
+            CheckKind::UpstreamPrs => "upstream-prs",
+            CheckKind::RemoteHealth => "remote-health",
+            CheckKind::RateLimited => "rate-limited",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CheckKind::Untracked => "Repository has untracked files",
+            CheckKind::Unstaged => "Repository has changes not staged for commit",
+            CheckKind::Ahead => "Branch is ahead of its remote",
+            CheckKind::NoRemote => "Repository has no remotes configured",
+            CheckKind::Branch => "Repository is not on a default branch",
+            CheckKind::OpenPrs => "Repository has open GitHub pull requests",
+            CheckKind::FetchFailed => "Fetching the repository's remotes failed",
+            CheckKind::Misplaced => "Repository is not in its canonical layout location",
+            CheckKind::BehindUpstream => "Fork's default branch is behind its upstream",
+            CheckKind::MergedUpstream => "Local branches were already merged upstream",
+            CheckKind::UpstreamPrs => "Fork has open pull requests to its upstream",
+            CheckKind::RemoteHealth => "A remote is unreachable, moved, archived or deleted",
+            CheckKind::RateLimited => "GitHub checks were skipped because of the API rate limit",
+        }
+    }
+
+    pub fn is_enabled(&self, filters: &RepoFilters) -> bool {
+        match self {
+            CheckKind::Untracked => filters.check_untracked,
+            CheckKind::Unstaged => filters.check_unstaged,
+            CheckKind::Ahead => filters.check_ahead,
+            CheckKind::NoRemote => filters.check_no_remotes,
+            CheckKind::Branch => filters.check_branch,
+            CheckKind::OpenPrs => filters.check_prs,
+            CheckKind::FetchFailed => filters.check_fetch,
+            CheckKind::Misplaced => filters.check_layout,
+            CheckKind::BehindUpstream | CheckKind::MergedUpstream | CheckKind::UpstreamPrs => {
+                filters.check_fork
+            }
+            CheckKind::RemoteHealth => filters.check_remote_health,
+            CheckKind::RateLimited => filters.check_prs || filters.check_fork,
+        }
+    }
+}
+
+impl FromStr for CheckKind {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        CheckKind::ALL
+            .iter()
+            .copied()
+            .find(|check| check.id() == id)
+            .ok_or_else(|| {
+                let ids: Vec<&str> = CheckKind::ALL.iter().map(|check| check.id()).collect();
+                format!(
+                    "unknown check '{}', expected one of: {}",
+                    id,
+                    ids.join(", ")
+                )
+            })
+    }
+}
+
+/// A single failed check for a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub check: CheckKind,
+    pub detail: String,
+}
+
+impl RepoStatus {
+    /// Result of a single check, `None` when the check passed
+    pub fn finding(&self, check: CheckKind) -> Option<Finding> {
+        let detail = match check {
+            CheckKind::Untracked if self.untracked_files => "untracked files found".to_string(),
+            CheckKind::Unstaged if self.unstaged_changes => {
+                "changes not staged for commit".to_string()
+            }
+            CheckKind::Ahead if self.ahead_of_remote => "branch ahead of remote".to_string(),
+            CheckKind::NoRemote if self.missing_remote => "repo missing remote".to_string(),
+            CheckKind::Branch => match &self.non_default_branch {
+                Some(branch) => format!("currently on a checked out branch: {}", branch),
+                None => return None,
+            },
+            CheckKind::OpenPrs if self.open_pr_count > 0 => {
+                format!("{} open pull requests", self.open_pr_count)
+            }
+            CheckKind::FetchFailed => match &self.fetch_error {
+                Some(error) => format!("fetch failed: {}", error),
+                None => return None,
+            },
+            CheckKind::Misplaced => match &self.canonical_path {
+                Some(path) => format!("not in canonical location, expected {}", path.display()),
+                None => return None,
+            },
+            CheckKind::BehindUpstream => match self.behind_upstream {
+                Some(behind) if behind > 0 => {
+                    format!("default branch {} commits behind upstream", behind)
+                }
+                _ => return None,
+            },
+            CheckKind::MergedUpstream if !self.merged_upstream.is_empty() => format!(
+                "branches already merged upstream: {}",
+                self.merged_upstream.join(", ")
+            ),
+            CheckKind::UpstreamPrs if !self.upstream_prs.is_empty() => {
+                format!("{} open pull requests to upstream", self.upstream_prs.len())
+            }
+            CheckKind::RemoteHealth if !self.remote_issues.is_empty() => {
+                let issues: Vec<String> = self
+                    .remote_issues
+                    .iter()
+                    .map(|issue| issue.to_string())
+                    .collect();
+                format!("remote problems: {}", issues.join("; "))
+            }
+            CheckKind::RateLimited if self.rate_limited => {
+                "rate limited, GitHub checks skipped".to_string()
+            }
+            _ => return None,
+        };
+
+        Some(Finding { check, detail })
+    }
+
+    pub fn is_acknowledged(&self, check: CheckKind) -> bool {
+        self.acknowledged.contains_key(&check)
+    }
+
+    /// Uncommitted work that is too recent to report under `dirty_warn_after`
+    pub fn is_fresh_dirt(&self, check: CheckKind, filters: &RepoFilters) -> bool {
+        let Some(threshold) = filters.dirty_warn_after else {
+            return false;
+        };
+
+        matches!(check, CheckKind::Untracked | CheckKind::Unstaged)
+            && self
+                .dirty_age(chrono::Utc::now().timestamp())
+                .is_some_and(|age| age < i64::try_from(threshold).unwrap_or(i64::MAX))
+    }
+
+    /// Finding for an enabled check that is neither acknowledged nor fresh dirt
+    pub fn active_finding(&self, check: CheckKind, filters: &RepoFilters) -> Option<Finding> {
+        if !check.is_enabled(filters)
+            || self.is_acknowledged(check)
+            || self.is_fresh_dirt(check, filters)
+        {
+            return None;
+        }
+        self.finding(check)
+    }
+
+    /// Findings for the checks enabled in `filters`, leaving out acknowledged ones
+    pub fn findings(&self, filters: &RepoFilters) -> Vec<Finding> {
+        CheckKind::ALL
+            .iter()
+            .filter_map(|check| self.active_finding(*check, filters))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::status::GitStatus;
+    use std::collections::HashSet;
+
+    #[test]
+    fn huge_dirty_thresholds_keep_fresh_dirt_quiet() {
+        let mut git_status = GitStatus::new();
+        git_status.untracked_files = true;
+        git_status.dirty_oldest_mtime = Some(chrono::Utc::now().timestamp());
+        let status = RepoStatus::new(git_status, Vec::new(), HashSet::new());
+
+        let mut filters = RepoFilters::git_only(Default::default());
+        for threshold in [7 * 24 * 60 * 60, i64::MAX as u64 + 1, u64::MAX] {
+            filters.dirty_warn_after = Some(threshold);
+            assert!(status.is_fresh_dirt(CheckKind::Untracked, &filters));
+            assert!(status
+                .active_finding(CheckKind::Untracked, &filters)
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn old_head_commits_keep_touched_dirt_stale() {
+        let now = chrono::Utc::now().timestamp();
+        let mut git_status = GitStatus::new();
+        git_status.unstaged_changes = true;
+        git_status.dirty_oldest_mtime = Some(now - 60);
+        git_status.head_commit_time = Some(now - 90 * 24 * 60 * 60);
+        let mut status = RepoStatus::new(git_status, Vec::new(), HashSet::new());
+
+        let mut filters = RepoFilters::git_only(Default::default());
+        filters.dirty_warn_after = Some(3 * 24 * 60 * 60);
+        assert!(status.dirty_age(now) >= Some(90 * 24 * 60 * 60));
+        assert!(status
+            .active_finding(CheckKind::Unstaged, &filters)
+            .is_some());
+
+        // A dirty file older than the last commit still counts from its own mtime
+        status.head_commit_time = Some(now - 60);
+        status.dirty_oldest_mtime = Some(now - 10 * 24 * 60 * 60);
+        assert_eq!(status.dirty_age(now), Some(10 * 24 * 60 * 60));
+
+        status.dirty_oldest_mtime = Some(now - 60);
+        assert!(status
+            .active_finding(CheckKind::Unstaged, &filters)
+            .is_none());
+    }
+}
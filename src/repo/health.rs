@@ -0,0 +1,323 @@
+use crate::git::remote::{list_remotes, ls_remote};
+use crate::git::url::with_remote_path;
+use crate::github::api::{fetch_github_repo_state, parse_github_url};
+use crate::github::client::GithubClient;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// What is wrong with a remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteProblem {
+    /// `git ls-remote` failed, with its error
+    Unreachable(String),
+    /// Renamed on GitHub, the remote URL only works through a redirect
+    Renamed {
+        url: String,
+    },
+    /// Moved to another owner on GitHub
+    Transferred {
+        url: String,
+    },
+    Archived,
+    /// Unreachable and unknown to GitHub even though we asked with a token
+    Deleted,
+}
+
+/// A problem with one remote of a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteIssue {
+    pub remote: String,
+    pub problem: RemoteProblem,
+}
+
+impl RemoteIssue {
+    /// URL the remote should be switched to, for moved repositories
+    pub fn new_url(&self) -> Option<&str> {
+        match &self.problem {
+            RemoteProblem::Renamed { url } | RemoteProblem::Transferred { url } => Some(url),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RemoteIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.problem {
+            RemoteProblem::Unreachable(error) => {
+                write!(f, "{} unreachable: {}", self.remote, error)
+            }
+            RemoteProblem::Renamed { url } => write!(f, "{} renamed, now {}", self.remote, url),
+            RemoteProblem::Transferred { url } => {
+                write!(f, "{} transferred, now {}", self.remote, url)
+            }
+            RemoteProblem::Archived => write!(f, "{} archived", self.remote),
+            RemoteProblem::Deleted => write!(f, "{} deleted", self.remote),
+        }
+    }
+}
+
+/// Check every remote of a repository
+///
+/// Each remote has to answer `git ls-remote` within `timeout`. GitHub remotes are
+/// also looked up through the API, which knows about renames, transfers and
+/// archived repositories that git happily keeps working with.
+pub async fn check_remote_health(
+    repo_path: &Path,
+    timeout: Duration,
+    github: &GithubClient,
+    debug: bool,
+) -> Vec<RemoteIssue> {
+    let mut issues = Vec::new();
+
+    for remote in list_remotes(repo_path) {
+        let reachable = ls_remote(repo_path, &remote.name, timeout).await;
+
+        let state = match fetch_github_repo_state(&remote.url, github, debug).await {
+            Ok(state) => state,
+            Err(e) => {
+                if debug && e.is_rate_limited() {
+                    println!("[-] GitHub lookup skipped for {}: {}", remote.url, e);
+                } else if debug {
+                    println!("[-] GitHub lookup failed for {}", remote.url);
+                }
+                None
+            }
+        };
+
+        let mut problems = Vec::new();
+        match (reachable, &state) {
+            // A private repository we can clone but not see through the API is fine,
+            // and without a token every private repository looks missing
+            (Err(_), Some(state)) if state.missing && github.is_authenticated() => {
+                problems.push(RemoteProblem::Deleted)
+            }
+            (Err(e), _) => problems.push(RemoteProblem::Unreachable(e)),
+            (Ok(()), _) => {}
+        }
+
+        if let Some(state) = state {
+            if let Some(moved_to) = &state.moved_to {
+                let old_owner = parse_github_url(&remote.url)
+                    .map(|(owner, _)| owner)
+                    .unwrap_or_default();
+                let url = with_remote_path(&remote.url, moved_to)
+                    .unwrap_or_else(|| format!("https://github.com/{}.git", moved_to));
+                let same_owner = moved_to
+                    .split_once('/')
+                    .is_some_and(|(owner, _)| owner.eq_ignore_ascii_case(&old_owner));
+                problems.push(if same_owner {
+                    RemoteProblem::Renamed { url }
+                } else {
+                    RemoteProblem::Transferred { url }
+                });
+            }
+            if state.archived {
+                problems.push(RemoteProblem::Archived);
+            }
+        }
+
+        issues.extend(problems.into_iter().map(|problem| RemoteIssue {
+            remote: remote.name.clone(),
+            problem,
+        }));
+    }
+
+    issues
+}
+
+/// Check the remotes of all repositories in parallel, at most `jobs` at a time
+///
+/// Returns the issues of every repository that has any.
+pub async fn check_remotes_health(
+    repos: &[PathBuf],
+    jobs: usize,
+    timeout: Duration,
+    github: &GithubClient,
+    debug: bool,
+) -> Result<HashMap<PathBuf, Vec<RemoteIssue>>, tokio::task::JoinError> {
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let unhealthy = Arc::new(Mutex::new(HashMap::new()));
+
+    let handles: Vec<_> = repos
+        .iter()
+        .map(|repo_path| {
+            let repo_path = repo_path.clone();
+            let permits = Arc::clone(&permits);
+            let unhealthy = Arc::clone(&unhealthy);
+            let github = github.clone();
+
+            tokio::spawn(async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+
+                if debug {
+                    println!("[-] Checking remotes of {}", repo_path.display());
+                }
+
+                let issues = check_remote_health(&repo_path, timeout, &github, debug).await;
+                if !issues.is_empty() {
+                    unhealthy.lock().unwrap().insert(repo_path, issues);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await?;
+    }
+
+    let unhealthy = std::mem::take(&mut *unhealthy.lock().unwrap());
+    Ok(unhealthy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::process::Command;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Serve canned GitHub API answers, `/repos/owner/old` was renamed and
+    /// archived, anything else is a 404
+    fn stub_github() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers, the requests have no body
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let (status, body) = if request_line.starts_with("GET /repos/owner/old ") {
+                    (
+                        "200 OK",
+                        r#"{"id": 1, "name": "new", "full_name": "owner/new", "archived": true,
+                            "url": "https://api.github.com/repos/owner/new"}"#,
+                    )
+                } else {
+                    ("404 Not Found", r#"{"message": "Not Found"}"#)
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    /// A repository whose remotes never reach the network, GitHub URLs fail through a dead proxy
+    fn repo_with_remotes(root: &Path, remotes: &[(&str, &str)]) -> PathBuf {
+        let repo = root.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "--quiet"]);
+        git(&repo, &["config", "http.proxy", "http://127.0.0.1:1"]);
+        for (name, url) in remotes {
+            git(&repo, &["remote", "add", name, url]);
+        }
+        repo
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gitgud-health-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn file_remotes_are_checked_with_ls_remote() {
+        let root = scratch_dir("file");
+        git(&root, &["init", "--quiet", "--bare", "origin.git"]);
+        let origin = format!("file://{}", root.join("origin.git").display());
+        let missing = format!("file://{}", root.join("missing.git").display());
+        let repo = repo_with_remotes(&root, &[("origin", &origin), ("old", &missing)]);
+
+        let github = GithubClient::with_base_uri(None, &stub_github()).unwrap();
+        let issues = check_remote_health(&repo, TIMEOUT, &github, false).await;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].remote, "old");
+        assert!(matches!(issues[0].problem, RemoteProblem::Unreachable(_)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn missing_github_repositories_are_only_deleted_with_a_token() {
+        let root = scratch_dir("deleted");
+        let repo = repo_with_remotes(&root, &[("origin", "https://github.com/owner/gone.git")]);
+        let api = stub_github();
+
+        let github = GithubClient::with_base_uri(Some("token"), &api).unwrap();
+        let issues = check_remote_health(&repo, TIMEOUT, &github, false).await;
+        assert_eq!(
+            issues,
+            vec![RemoteIssue {
+                remote: "origin".to_string(),
+                problem: RemoteProblem::Deleted,
+            }]
+        );
+
+        // Private repositories 404 without a token, that proves nothing
+        let github = GithubClient::with_base_uri(None, &api).unwrap();
+        let issues = check_remote_health(&repo, TIMEOUT, &github, false).await;
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0].problem, RemoteProblem::Unreachable(_)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_renamed_and_archived_github_repositories() {
+        let root = scratch_dir("renamed");
+        let repo = repo_with_remotes(&root, &[("origin", "https://github.com/owner/old.git")]);
+
+        let github = GithubClient::with_base_uri(Some("token"), &stub_github()).unwrap();
+        let problems: Vec<RemoteProblem> = check_remote_health(&repo, TIMEOUT, &github, false)
+            .await
+            .into_iter()
+            .map(|issue| issue.problem)
+            .collect();
+
+        assert_eq!(problems.len(), 3);
+        assert!(matches!(problems[0], RemoteProblem::Unreachable(_)));
+        assert_eq!(
+            problems[1],
+            RemoteProblem::Renamed {
+                url: "https://github.com/owner/new.git".to_string()
+            }
+        );
+        assert_eq!(problems[2], RemoteProblem::Archived);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
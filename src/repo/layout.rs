@@ -0,0 +1,105 @@
+use crate::config::RemoteNames;
+use crate::git::remote::primary_remote;
+use crate::git::url::{parse_remote_url, RemoteLocation};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How deep below the root repositories are looked for, enough for `host/group/sub/repo`
+const MAX_DEPTH: usize = 5;
+
+/// Where a repository belongs in the `<root>/<host>/<owner>/<repo>` layout, based on its primary remote
+pub fn canonical_path(root: &Path, repo_path: &Path, remotes: &RemoteNames) -> Option<PathBuf> {
+    let location = parse_remote_url(&primary_remote(repo_path, remotes)?.url)?;
+    location_path(root, &location)
+}
+
+/// `<root>/<host>/<path>`, or `None` when a segment would leave the root or is empty
+fn location_path(root: &Path, location: &RemoteLocation) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for segment in std::iter::once(location.host.as_str()).chain(location.path.split('/')) {
+        if matches!(segment, "" | "." | "..") || segment.contains('\\') {
+            return None;
+        }
+        path.push(segment);
+    }
+    Some(path)
+}
+
+/// The canonical location of a repository if it lives somewhere else
+pub fn misplaced(root: &Path, repo_path: &Path, remotes: &RemoteNames) -> Option<PathBuf> {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let current = fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.to_path_buf());
+
+    canonical_path(&root, repo_path, remotes).filter(|canonical| *canonical != current)
+}
+
+fn walk(dir: &Path, depth: usize, repos: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false)
+            || entry.file_name().to_string_lossy().starts_with('.')
+        {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.join(".git").is_dir() {
+            repos.push(path);
+        } else if depth < MAX_DEPTH {
+            // Unreadable directories below the root are not worth failing the scan for
+            let _ = walk(&path, depth + 1, repos);
+        }
+    }
+    Ok(())
+}
+
+/// Find git repositories anywhere below `root`, without descending into repositories
+pub fn discover_nested_repos(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut repos = Vec::new();
+    walk(root, 1, &mut repos)?;
+    repos.sort();
+    Ok(repos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(host: &str, path: &str) -> RemoteLocation {
+        RemoteLocation {
+            host: host.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn builds_host_owner_repo_paths() {
+        let root = Path::new("/src");
+        assert_eq!(
+            location_path(root, &location("github.com", "owner/repo")),
+            Some(PathBuf::from("/src/github.com/owner/repo"))
+        );
+        assert_eq!(
+            location_path(root, &location("gitlab.com", "group/sub/repo")),
+            Some(PathBuf::from("/src/gitlab.com/group/sub/repo"))
+        );
+    }
+
+    #[test]
+    fn rejects_segments_that_leave_the_root() {
+        let root = Path::new("/src");
+        for (host, path) in [
+            ("github.com", "owner/../../etc"),
+            ("github.com", "../repo"),
+            ("github.com", "owner//repo"),
+            ("github.com", "owner/./repo"),
+            ("..", "owner/repo"),
+            ("", "owner/repo"),
+            ("github.com", "owner\\..\\repo"),
+        ] {
+            assert_eq!(location_path(root, &location(host, path)), None, "{}", path);
+        }
+
+        let parsed = parse_remote_url("git@github.com:owner/../../../tmp/repo.git").unwrap();
+        assert_eq!(location_path(root, &parsed), None);
+    }
+}
@@ -1,2 +1,9 @@
+pub mod cache;
+pub mod diff;
+pub mod fetch;
 pub mod filters;
+pub mod findings;
+pub mod health;
+pub mod layout;
+pub mod scan;
 pub mod status;
@@ -0,0 +1,271 @@
+use crate::ack::AckStore;
+use crate::config::GithubBackend;
+use crate::git::status::{check_git_status, GitStatus};
+use crate::github::api::{fetch_github_prs, fetch_upstream_info};
+use crate::github::client::{GithubClient, GithubError};
+use crate::github::graphql::fetch_github_prs_batched;
+use crate::github::pr::PullRequestListing;
+use crate::repo::cache::{ignored_dirs, Fingerprint, ScanCache};
+use crate::repo::filters::RepoFilters;
+use crate::repo::layout::{discover_nested_repos, misplaced};
+use crate::repo::status::RepoStatus;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Everything needed to check a single repository
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub filters: RepoFilters,
+    /// GitHub client shared by all lookups, so they respect one rate limit
+    pub github: GithubClient,
+    /// Most open pull requests listed per repository
+    pub max_prs: usize,
+    pub github_backend: GithubBackend,
+    pub protected_branches: HashSet<String>,
+    pub debug: bool,
+    /// Skip git invocations for repositories that did not change since the last run
+    pub cache: Option<Arc<Mutex<ScanCache>>>,
+    /// Acknowledged findings to attach to each repository
+    pub acks: Arc<AckStore>,
+    /// Root of the canonical `<host>/<owner>/<repo>` layout, when it is enforced
+    pub layout_root: Option<PathBuf>,
+}
+
+/// Stable identity of a repository, its absolute path, so relative scan roots agree
+pub fn repo_key(repo_path: &Path) -> String {
+    fs::canonicalize(repo_path)
+        .unwrap_or_else(|_| repo_path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Find the git repositories directly below `root`
+pub fn discover_repos(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut repos: Vec<PathBuf> = fs::read_dir(root)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter(|entry| entry.path().join(".git").is_dir())
+        .map(|entry| entry.path())
+        .collect();
+
+    repos.sort();
+    Ok(repos)
+}
+
+/// Repositories covered by a scan of `root`, the canonical layout nests them
+/// below host and owner directories
+pub fn discover_workspace(root: &Path, filters: &RepoFilters) -> std::io::Result<Vec<PathBuf>> {
+    if filters.check_layout {
+        discover_nested_repos(root)
+    } else {
+        discover_repos(root)
+    }
+}
+
+/// `check_git_status`, answered from the scan cache when the fingerprint still matches
+fn cached_git_status(repo_path: &Path, options: &ScanOptions) -> GitStatus {
+    let filters = &options.filters;
+    let debug = options.debug;
+
+    let Some(cache) = &options.cache else {
+        return check_git_status(repo_path, filters, debug);
+    };
+
+    let ignored = cache.lock().unwrap().ignored_dirs(repo_path);
+    let Some(fingerprint) = Fingerprint::compute(repo_path, &ignored) else {
+        return check_git_status(repo_path, filters, debug);
+    };
+
+    if let Some(status) = cache
+        .lock()
+        .unwrap()
+        .lookup(repo_path, &fingerprint, filters)
+    {
+        if debug {
+            println!("[-] Using cached status for {}", repo_path.display());
+        }
+        return status;
+    }
+
+    // Fingerprint before checking, so changes made meanwhile invalidate the entry
+    let now_ignored = ignored_dirs(repo_path);
+    let fingerprint = if now_ignored == ignored {
+        fingerprint
+    } else {
+        Fingerprint::compute(repo_path, &now_ignored).unwrap_or(fingerprint)
+    };
+    let status = check_git_status(repo_path, filters, debug);
+    cache
+        .lock()
+        .unwrap()
+        .store(repo_path, fingerprint, now_ignored, filters, &status);
+    status
+}
+
+/// Run the git checks for one repository, with its pull requests already looked up
+///
+/// `listing` is `None` when pull requests aren't checked.
+async fn scan_repo(
+    repo_path: &Path,
+    options: &ScanOptions,
+    listing: Option<Result<PullRequestListing, GithubError>>,
+) -> RepoStatus {
+    let filters = &options.filters;
+    let debug = options.debug;
+
+    if debug {
+        println!("[-] Checking repository {}", repo_path.display());
+    }
+
+    // Get git status
+    let git_status = cached_git_status(repo_path, options);
+
+    // Get GitHub PR info if needed
+    let mut prs = Vec::new();
+    let mut pr_count = 0;
+    let mut rate_limited = false;
+    if let Some(listing) = listing {
+        match listing {
+            Ok(listing) => {
+                if debug {
+                    println!(
+                        "[-] Found {} open PRs for {}",
+                        listing.total,
+                        repo_path.display()
+                    );
+                }
+
+                // Filter draft PRs if needed
+                let listed = listing.prs.len();
+                prs = if filters.include_draft_prs {
+                    listing.prs
+                } else {
+                    listing.prs.into_iter().filter(|pr| !pr.is_draft).collect()
+                };
+                // Drafts beyond the listed ones can't be told apart and stay counted
+                pr_count = listing.total - (listed - prs.len());
+            }
+            Err(e) if e.is_rate_limited() => rate_limited = true,
+            Err(_) if debug => {
+                println!("[-] Failed to fetch PRs for {}", repo_path.display());
+            }
+            Err(_) => {}
+        }
+    }
+
+    // Combine into repo status
+    let mut repo_status = RepoStatus::new(git_status, prs, options.protected_branches.clone());
+    repo_status.acknowledged = options.acks.active_for(repo_path);
+    repo_status.open_pr_count = pr_count;
+    repo_status.rate_limited = rate_limited;
+    if filters.check_fork {
+        match fetch_upstream_info(
+            repo_path,
+            &filters.remotes,
+            &options.github,
+            options.max_prs,
+            debug,
+        )
+        .await
+        {
+            Ok(Some(upstream)) => {
+                repo_status.upstream_repo = Some(upstream.repo);
+                repo_status.upstream_prs = if filters.include_draft_prs {
+                    upstream.prs
+                } else {
+                    upstream.prs.into_iter().filter(|pr| !pr.is_draft).collect()
+                };
+                // Only GitHub knows how far behind we are without a local upstream remote
+                if repo_status.behind_upstream.is_none() {
+                    repo_status.behind_upstream = upstream.behind_by;
+                }
+            }
+            Ok(None) => {}
+            Err(e) if e.is_rate_limited() => repo_status.rate_limited = true,
+            Err(_) if debug => {
+                println!("[-] Failed to fetch upstream for {}", repo_path.display());
+            }
+            Err(_) => {}
+        }
+    }
+    if let Some(root) = &options.layout_root {
+        repo_status.canonical_path = misplaced(root, repo_path, &filters.remotes);
+    }
+    repo_status
+}
+
+/// Scan all repositories in parallel, results are sorted by path
+///
+/// Also the way to re-check a few repositories, so their pull requests are
+/// looked up the same way as in a full scan.
+pub async fn scan_repos(
+    repos: &[PathBuf],
+    options: &ScanOptions,
+) -> Result<Vec<(PathBuf, RepoStatus)>, tokio::task::JoinError> {
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    // GraphQL asks for the pull requests of many repositories at once, up front
+    let mut batched =
+        if options.filters.check_prs && options.github_backend == GithubBackend::Graphql {
+            fetch_github_prs_batched(
+                repos,
+                &options.filters.remotes,
+                &options.github,
+                options.max_prs,
+                options.debug,
+            )
+            .await
+        } else {
+            HashMap::new()
+        };
+
+    let handles: Vec<_> = repos
+        .iter()
+        .map(|repo_path| {
+            let repo_path = repo_path.clone();
+            let options = options.clone();
+            let results = Arc::clone(&results);
+            let listing = batched.remove(&repo_path);
+
+            tokio::spawn(async move {
+                let prs = match listing {
+                    Some(listing) => Some(listing),
+                    None if options.filters.check_prs => Some(
+                        fetch_github_prs(
+                            &repo_path,
+                            &options.filters.remotes,
+                            &options.github,
+                            options.max_prs,
+                            options.debug,
+                        )
+                        .await,
+                    ),
+                    None => None,
+                };
+                let repo_status = scan_repo(&repo_path, &options, prs).await;
+                results.lock().unwrap().push((repo_path, repo_status));
+            })
+        })
+        .collect();
+
+    // Wait for all tasks to complete
+    for handle in handles {
+        handle.await?;
+    }
+
+    let mut results = std::mem::take(&mut *results.lock().unwrap());
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(results)
+}
+
+/// Scan a single repository again, like `scan_repos` would
+pub async fn rescan_repo(
+    repo_path: &Path,
+    options: &ScanOptions,
+) -> Result<RepoStatus, tokio::task::JoinError> {
+    let mut results = scan_repos(&[repo_path.to_path_buf()], options).await?;
+    let (_, status) = results.pop().expect("one result per repository");
+    Ok(status)
+}
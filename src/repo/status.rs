@@ -1,18 +1,51 @@
+use crate::ack::Acknowledgement;
 use crate::git::status::GitStatus;
 use crate::github::pr::PullRequestInfo;
 use crate::repo::filters::RepoFilters;
-use std::collections::HashSet;
+use crate::repo::findings::CheckKind;
+use crate::repo::health::RemoteIssue;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
+#[derive(Debug, Clone)]
 pub struct RepoStatus {
     // Git status
     pub untracked_files: bool,
     pub unstaged_changes: bool,
     pub ahead_of_remote: bool,
+    pub ahead_commits: u32,
     pub missing_remote: bool,
+    pub current_branch: String,
     pub non_default_branch: Option<String>,
+    pub dirty_oldest_mtime: Option<i64>,
+    pub head_commit_time: Option<i64>,
 
     // GitHub PR information
     pub open_prs: Vec<PullRequestInfo>,
+    // All open pull requests, `open_prs` only holds the first `max_prs`
+    pub open_pr_count: usize,
+
+    // GitHub lookups were skipped because the API rate limit ran out
+    pub rate_limited: bool,
+
+    // Fork checks: commits the default branch is behind upstream, local branches
+    // already merged there and our open pull requests to the upstream repository
+    pub behind_upstream: Option<u32>,
+    pub merged_upstream: Vec<String>,
+    pub upstream_repo: Option<String>,
+    pub upstream_prs: Vec<PullRequestInfo>,
+
+    // Why `git fetch` failed, only set when remotes were fetched before checking
+    pub fetch_error: Option<String>,
+
+    // Unreachable, moved, archived or deleted remotes, only set when remote health is checked
+    pub remote_issues: Vec<RemoteIssue>,
+
+    // Where the canonical layout expects the repository, only set when it lives elsewhere
+    pub canonical_path: Option<PathBuf>,
+
+    // Findings the user accepted for now, ignored by `has_issues`
+    pub acknowledged: HashMap<CheckKind, Acknowledgement>,
 }
 
 impl RepoStatus {
@@ -34,18 +67,44 @@ impl RepoStatus {
             untracked_files: git_status.untracked_files,
             unstaged_changes: git_status.unstaged_changes,
             ahead_of_remote: git_status.ahead_of_remote,
+            ahead_commits: git_status.ahead_commits,
             missing_remote: git_status.missing_remote,
+            current_branch: git_status.current_branch,
             non_default_branch,
+            dirty_oldest_mtime: git_status.dirty_oldest_mtime,
+            head_commit_time: git_status.head_commit_time,
+            open_pr_count: prs.len(),
             open_prs: prs,
+            rate_limited: false,
+            behind_upstream: git_status.behind_upstream,
+            // Long-lived branches are expected to be merged upstream over and over
+            merged_upstream: git_status
+                .merged_upstream
+                .into_iter()
+                .filter(|branch| !protected_branches.contains(branch))
+                .collect(),
+            upstream_repo: None,
+            upstream_prs: Vec::new(),
+            fetch_error: None,
+            remote_issues: Vec::new(),
+            canonical_path: None,
+            acknowledged: HashMap::new(),
         }
     }
 
+    /// Seconds the uncommitted work has gone without a commit
+    ///
+    /// Measured from the oldest dirty file or the HEAD commit, whichever is
+    /// earlier, so touching files in a long-abandoned repository keeps it stale.
+    pub fn dirty_age(&self, now: i64) -> Option<i64> {
+        let oldest = self.dirty_oldest_mtime?;
+        let since = self
+            .head_commit_time
+            .map_or(oldest, |head| oldest.min(head));
+        Some((now - since).max(0))
+    }
+
     pub fn has_issues(&self, filters: &RepoFilters) -> bool {
-        (filters.check_untracked && self.untracked_files)
-            || (filters.check_unstaged && self.unstaged_changes)
-            || (filters.check_ahead && self.ahead_of_remote)
-            || (filters.check_no_remotes && self.missing_remote)
-            || (filters.check_branch && self.non_default_branch.is_some())
-            || (filters.check_prs && !self.open_prs.is_empty())
+        !self.findings(filters).is_empty()
     }
 }
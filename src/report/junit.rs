@@ -0,0 +1,183 @@
+use crate::repo::filters::RepoFilters;
+use crate::repo::findings::CheckKind;
+use crate::repo::status::RepoStatus;
+use crate::report::escape_xml;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Render scan results as JUnit XML, one testcase per repository per enabled check
+pub fn render_junit(results: &[(PathBuf, RepoStatus)], filters: &RepoFilters) -> String {
+    let checks: Vec<CheckKind> = CheckKind::ALL
+        .iter()
+        .copied()
+        .filter(|check| check.is_enabled(filters))
+        .collect();
+
+    let mut suites = String::new();
+    let mut total_tests = 0;
+    let mut total_failures = 0;
+
+    for (repo_path, status) in results {
+        let repo = escape_xml(&repo_path.display().to_string());
+        let mut cases = String::new();
+        let mut failures = 0;
+
+        for check in &checks {
+            if let Some(ack) = status.acknowledged.get(check) {
+                let _ = writeln!(
+                    cases,
+                    "    <testcase classname=\"{}\" name=\"{}\">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                    repo,
+                    check.id(),
+                    escape_xml(&format!("acknowledged until {}: {}", ack.until, ack.reason))
+                );
+                continue;
+            }
+
+            match status.active_finding(*check, filters) {
+                Some(finding) => {
+                    failures += 1;
+                    let _ = writeln!(
+                        cases,
+                        "    <testcase classname=\"{}\" name=\"{}\">\n      <failure type=\"{}\" message=\"{}\"/>\n    </testcase>",
+                        repo,
+                        check.id(),
+                        check.id(),
+                        escape_xml(&finding.detail)
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        cases,
+                        "    <testcase classname=\"{}\" name=\"{}\"/>",
+                        repo,
+                        check.id()
+                    );
+                }
+            }
+        }
+
+        total_tests += checks.len();
+        total_failures += failures;
+
+        let _ = write!(
+            suites,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}  </testsuite>\n",
+            repo,
+            checks.len(),
+            failures,
+            cases
+        );
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"gitgud\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+        total_tests, total_failures, suites
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ack::Acknowledgement;
+    use crate::git::status::GitStatus;
+    use chrono::NaiveDate;
+    use std::collections::HashSet;
+
+    /// Check that tags nest and every `&` and `<` is escaped, returns the opened elements
+    fn assert_well_formed(xml: &str) -> Vec<String> {
+        let mut open: Vec<String> = Vec::new();
+        let mut elements = Vec::new();
+        let mut rest = xml
+            .strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")
+            .expect("XML declaration");
+
+        let check_escapes = |text: &str| {
+            for (i, _) in text.match_indices('&') {
+                assert!(
+                    ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"]
+                        .iter()
+                        .any(|entity| text[i..].starts_with(entity)),
+                    "unescaped & in {:?}",
+                    text
+                );
+            }
+        };
+
+        while let Some(start) = rest.find('<') {
+            check_escapes(&rest[..start]);
+            let end = start + rest[start..].find('>').expect("unclosed tag");
+            let tag = &rest[start + 1..end];
+            assert!(!tag.contains('<'), "unescaped < in {:?}", tag);
+            assert_eq!(
+                tag.matches('"').count() % 2,
+                0,
+                "unbalanced quotes in {:?}",
+                tag
+            );
+            check_escapes(tag);
+
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(name), "mismatched </{}>", name);
+            } else {
+                let name = tag.split([' ', '/']).next().unwrap().to_string();
+                elements.push(name.clone());
+                if !tag.ends_with('/') {
+                    open.push(name);
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        assert!(rest.trim().is_empty());
+        assert!(open.is_empty(), "unclosed {:?}", open);
+        elements
+    }
+
+    #[test]
+    fn renders_escaped_well_formed_xml() {
+        let mut git_status = GitStatus::new();
+        git_status.untracked_files = true;
+        let mut status = RepoStatus::new(git_status, Vec::new(), HashSet::new());
+        let day = NaiveDate::from_ymd_opt(2100, 1, 1).unwrap();
+        status.acknowledged.insert(
+            CheckKind::Branch,
+            Acknowledgement {
+                repo: String::new(),
+                check: CheckKind::Branch.id().to_string(),
+                until: day,
+                reason: "waiting on <review> & \"sign-off\"".to_string(),
+                created: day,
+            },
+        );
+        let filters = RepoFilters::git_only(Default::default());
+        let results = vec![(PathBuf::from("/src/a&b <c>"), status)];
+
+        let xml = render_junit(&results, &filters);
+        let elements = assert_well_formed(&xml);
+
+        assert_eq!(
+            elements,
+            [
+                "testsuites",
+                "testsuite",
+                "testcase",
+                "failure",
+                "testcase",
+                "testcase",
+                "testcase",
+                "testcase",
+                "skipped",
+            ]
+        );
+        assert!(xml.contains("<testsuites name=\"gitgud\" tests=\"5\" failures=\"1\">"));
+        assert!(
+            xml.contains("<testsuite name=\"/src/a&amp;b &lt;c&gt;\" tests=\"5\" failures=\"1\">")
+        );
+        assert!(xml.contains(
+            "<testcase classname=\"/src/a&amp;b &lt;c&gt;\" name=\"untracked\">\n      <failure type=\"untracked\" message=\"untracked files found\"/>"
+        ));
+        assert!(xml.contains(
+            "<skipped message=\"acknowledged until 2100-01-01: waiting on &lt;review&gt; &amp; &quot;sign-off&quot;\"/>"
+        ));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::repo::filters::RepoFilters;
+use crate::repo::status::RepoStatus;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Keep every field on one line so the columns stay parseable
+fn sanitize(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Render one `path<TAB>check<TAB>detail` line per finding
+pub fn render_porcelain(results: &[(PathBuf, RepoStatus)], filters: &RepoFilters) -> String {
+    let mut out = String::new();
+    for (repo_path, status) in results {
+        let path = sanitize(&repo_path.display().to_string());
+        for finding in status.findings(filters) {
+            let _ = writeln!(
+                out,
+                "{}\t{}\t{}",
+                path,
+                finding.check.id(),
+                sanitize(&finding.detail)
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::status::GitStatus;
+    use std::collections::HashSet;
+
+    #[test]
+    fn renders_one_sanitized_line_per_finding() {
+        let mut git_status = GitStatus::new();
+        git_status.untracked_files = true;
+        git_status.unstaged_changes = true;
+        git_status.current_branch = "feature".to_string();
+        git_status.is_default_branch = false;
+        let mut dirty = RepoStatus::new(git_status, Vec::new(), HashSet::new());
+        dirty.fetch_error = Some("could not read\nfrom remote\trepository\r".to_string());
+        let clean = RepoStatus::new(GitStatus::new(), Vec::new(), HashSet::new());
+
+        let mut filters = RepoFilters::git_only(Default::default());
+        filters.check_fetch = true;
+        let results = vec![
+            (PathBuf::from("/src/we\tird\nrepo"), dirty),
+            (PathBuf::from("/src/clean"), clean),
+        ];
+
+        assert_eq!(
+            render_porcelain(&results, &filters),
+            "/src/we ird repo\tuntracked\tuntracked files found\n\
+             /src/we ird repo\tunstaged\tchanges not staged for commit\n\
+             /src/we ird repo\tbranch\tcurrently on a checked out branch: feature\n\
+             /src/we ird repo\tfetch-failed\tfetch failed: could not read from remote repository \n"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::repo::filters::RepoFilters;
+use crate::repo::findings::CheckKind;
+use crate::repo::status::RepoStatus;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+/// Render scan results in the Prometheus text exposition format
+pub fn render_prometheus(
+    results: &[(PathBuf, RepoStatus)],
+    filters: &RepoFilters,
+    repo_count: usize,
+    scan_duration: Duration,
+) -> String {
+    let mut out = String::new();
+
+    write_header(
+        &mut out,
+        "gitgud_repo_issues",
+        "Whether a check currently fails for a repository (1) or not (0)",
+    );
+    for (repo_path, status) in results {
+        let repo = escape_label(&repo_path.display().to_string());
+        for check in CheckKind::ALL
+            .iter()
+            .filter(|check| check.is_enabled(filters))
+        {
+            let value = u8::from(status.active_finding(*check, filters).is_some());
+            let _ = writeln!(
+                out,
+                "gitgud_repo_issues{{repo=\"{}\",check=\"{}\"}} {}",
+                repo,
+                check.id(),
+                value
+            );
+        }
+    }
+
+    if filters.check_ahead {
+        write_header(
+            &mut out,
+            "gitgud_repo_ahead_commits",
+            "Number of local commits not pushed to the upstream branch",
+        );
+        for (repo_path, status) in results {
+            let _ = writeln!(
+                out,
+                "gitgud_repo_ahead_commits{{repo=\"{}\"}} {}",
+                escape_label(&repo_path.display().to_string()),
+                status.ahead_commits
+            );
+        }
+    }
+
+    if filters.check_untracked || filters.check_unstaged {
+        write_header(
+            &mut out,
+            "gitgud_repo_dirty_age_seconds",
+            "Seconds uncommitted work has gone without a commit, from its oldest change or the last commit",
+        );
+        let now = chrono::Utc::now().timestamp();
+        for (repo_path, status) in results {
+            if let Some(age) = status.dirty_age(now) {
+                let _ = writeln!(
+                    out,
+                    "gitgud_repo_dirty_age_seconds{{repo=\"{}\"}} {}",
+                    escape_label(&repo_path.display().to_string()),
+                    age
+                );
+            }
+        }
+    }
+
+    if filters.check_prs {
+        write_header(
+            &mut out,
+            "gitgud_open_prs",
+            "Number of open GitHub pull requests for a repository",
+        );
+        for (repo_path, status) in results {
+            let _ = writeln!(
+                out,
+                "gitgud_open_prs{{repo=\"{}\"}} {}",
+                escape_label(&repo_path.display().to_string()),
+                status.open_pr_count
+            );
+        }
+    }
+
+    write_header(
+        &mut out,
+        "gitgud_repositories_scanned",
+        "Number of git repositories found during the last scan",
+    );
+    let _ = writeln!(out, "gitgud_repositories_scanned {}", repo_count);
+
+    write_header(
+        &mut out,
+        "gitgud_scan_duration_seconds",
+        "Wall clock time taken by the last scan",
+    );
+    let _ = writeln!(
+        out,
+        "gitgud_scan_duration_seconds {:.3}",
+        scan_duration.as_secs_f64()
+    );
+
+    out
+}
+
+/// Write metrics for the node_exporter textfile collector
+///
+/// The content goes to a temporary file next to `path` first and is then renamed
+/// into place, so the collector never reads a half written file.
+pub fn write_textfile(path: &Path, metrics: &str) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "gitgud.prom".to_string());
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    fs::write(&tmp_path, metrics)?;
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::status::GitStatus;
+    use std::collections::HashSet;
+
+    #[test]
+    fn renders_the_exposition_format() {
+        let mut git_status = GitStatus::new();
+        git_status.untracked_files = true;
+        git_status.ahead_commits = 2;
+        git_status.dirty_oldest_mtime = Some(chrono::Utc::now().timestamp() - 60);
+        let status = RepoStatus::new(git_status, Vec::new(), HashSet::new());
+        let filters = RepoFilters::git_only(Default::default());
+        let results = vec![(PathBuf::from("/src/we\\ird \"repo\"\nname"), status)];
+
+        let metrics = render_prometheus(&results, &filters, 1, Duration::from_millis(1500));
+
+        for name in [
+            "gitgud_repo_issues",
+            "gitgud_repo_ahead_commits",
+            "gitgud_repo_dirty_age_seconds",
+            "gitgud_repositories_scanned",
+            "gitgud_scan_duration_seconds",
+        ] {
+            assert!(metrics.contains(&format!("# HELP {} ", name)), "{}", name);
+            assert!(
+                metrics.contains(&format!("# TYPE {} gauge\n", name)),
+                "{}",
+                name
+            );
+        }
+        assert!(!metrics.contains("gitgud_open_prs"));
+
+        let repo = r#"repo="/src/we\\ird \"repo\"\nname""#;
+        assert!(metrics.contains(&format!(
+            "gitgud_repo_issues{{{},check=\"untracked\"}} 1\n",
+            repo
+        )));
+        assert!(metrics.contains(&format!(
+            "gitgud_repo_issues{{{},check=\"unstaged\"}} 0\n",
+            repo
+        )));
+        assert!(metrics.contains(&format!("gitgud_repo_ahead_commits{{{}}} 2\n", repo)));
+        assert!(metrics.contains("gitgud_repositories_scanned 1\n"));
+        assert!(metrics.contains("gitgud_scan_duration_seconds 1.500\n"));
+        // Every sample is one line, the newline in the name is escaped
+        assert!(metrics
+            .lines()
+            .all(|line| line.starts_with("# ") || line.starts_with("gitgud_")));
+    }
+
+    #[test]
+    fn textfile_is_renamed_into_place() {
+        let dir = std::env::temp_dir().join(format!("gitgud-prom-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("gitgud.prom");
+
+        fs::write(&path, "old\n").unwrap();
+        write_textfile(&path, "new\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+
+        // A failed rename leaves the target alone and cleans up the temporary file
+        let blocked = dir.join("blocked.prom");
+        fs::create_dir_all(blocked.join("not-empty")).unwrap();
+        assert!(write_textfile(&blocked, "new\n").is_err());
+
+        let entries: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries.len(), 2, "{:?}", entries);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
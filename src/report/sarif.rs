@@ -0,0 +1,105 @@
+use crate::display::percent_encode_path;
+use crate::repo::filters::RepoFilters;
+use crate::repo::findings::CheckKind;
+use crate::repo::status::RepoStatus;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn sarif_level(check: CheckKind) -> &'static str {
+    match check {
+        CheckKind::Ahead
+        | CheckKind::NoRemote
+        | CheckKind::FetchFailed
+        | CheckKind::RemoteHealth => "error",
+        CheckKind::Untracked
+        | CheckKind::Unstaged
+        | CheckKind::BehindUpstream
+        | CheckKind::RateLimited => "warning",
+        CheckKind::Branch
+        | CheckKind::OpenPrs
+        | CheckKind::Misplaced
+        | CheckKind::MergedUpstream
+        | CheckKind::UpstreamPrs => "note",
+    }
+}
+
+fn artifact_uri(repo_path: &Path) -> String {
+    let path = percent_encode_path(repo_path);
+    if repo_path.is_absolute() {
+        format!("file://{}", path)
+    } else {
+        path
+    }
+}
+
+/// Render scan results as a SARIF 2.1.0 log with one rule per check kind
+pub fn render_sarif(results: &[(PathBuf, RepoStatus)], filters: &RepoFilters) -> String {
+    let rules: Vec<Value> = CheckKind::ALL
+        .iter()
+        .map(|check| {
+            json!({
+                "id": check.id(),
+                "shortDescription": { "text": check.description() },
+                "defaultConfiguration": { "level": sarif_level(*check) },
+            })
+        })
+        .collect();
+
+    let mut sarif_results = Vec::new();
+    for (repo_path, status) in results {
+        for finding in status.findings(filters) {
+            let rule_index = CheckKind::ALL
+                .iter()
+                .position(|check| *check == finding.check)
+                .unwrap_or_default();
+
+            sarif_results.push(json!({
+                "ruleId": finding.check.id(),
+                "ruleIndex": rule_index,
+                "level": sarif_level(finding.check),
+                "message": {
+                    "text": format!("{} => {}", repo_path.display(), finding.detail)
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": artifact_uri(repo_path) }
+                    }
+                }],
+            }));
+        }
+    }
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "gitgud",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/DanceMore/gitgud",
+                    "rules": rules,
+                }
+            },
+            "results": sarif_results,
+        }]
+    });
+
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_uris_are_encoded() {
+        assert_eq!(
+            artifact_uri(Path::new("/src/my repo#2")),
+            "file:///src/my%20repo%232"
+        );
+        assert_eq!(artifact_uri(Path::new("work/ré?")), "work/r%C3%A9%3F");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::config::RemoteNames;
+use crate::config::WorkspaceManifest;
+use crate::display::path_link;
+use crate::git::remote::{clone_repo, primary_remote};
+use crate::git::url::normalize_remote_url;
+use crate::repo::scan::repo_key;
+use colored::Colorize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Compare URLs without caring about the protocol, case, a trailing slash or `.git`
+fn same_url(a: &str, b: &str) -> bool {
+    normalize_remote_url(a) == normalize_remote_url(b)
+}
+
+/// Clone the manifest repositories missing below `root` and report what doesn't match
+pub fn run_sync(
+    root: &Path,
+    repos: &[PathBuf],
+    manifest: &WorkspaceManifest,
+    remotes: &RemoteNames,
+    debug: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut cloned, mut mismatched, mut failed) = (0, 0, 0);
+    let mut expected = HashSet::new();
+
+    for repo in &manifest.repos {
+        let target = match repo.target_path() {
+            Ok(path) => root.join(path),
+            Err(e) => {
+                failed += 1;
+                println!("{}", format!("[!] {}", e).red().bold());
+                continue;
+            }
+        };
+        expected.insert(repo_key(&target));
+        let path = path_link(&target);
+
+        if target.join(".git").exists() {
+            match primary_remote(&target, remotes) {
+                Some(remote) if same_url(&remote.url, &repo.url) => {
+                    if debug {
+                        println!("[-] {} is in sync", target.display());
+                    }
+                }
+                Some(remote) => {
+                    mismatched += 1;
+                    println!(
+                        "{}",
+                        format!(
+                            "[~] {} => {} is {}, manifest expects {}",
+                            path, remote.name, remote.url, repo.url
+                        )
+                        .yellow()
+                        .bold()
+                    );
+                }
+                None => {
+                    mismatched += 1;
+                    println!(
+                        "{}",
+                        format!("[~] {} => no remotes, manifest expects {}", path, repo.url)
+                            .yellow()
+                            .bold()
+                    );
+                }
+            }
+            continue;
+        }
+
+        if target.exists() {
+            failed += 1;
+            println!(
+                "{}",
+                format!("[!] {} => exists but is not a git repository", path)
+                    .red()
+                    .bold()
+            );
+            continue;
+        }
+
+        match clone_repo(&repo.url, &target) {
+            Ok(()) => {
+                cloned += 1;
+                println!(
+                    "{}",
+                    format!("[+] {} => cloned from {}", path, repo.url)
+                        .green()
+                        .bold()
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                println!(
+                    "{}",
+                    format!("[!] {} => unable to clone {}: {}", path, repo.url, e)
+                        .red()
+                        .bold()
+                );
+            }
+        }
+    }
+
+    let mut unlisted = 0;
+    for repo_path in repos {
+        if expected.contains(&repo_key(repo_path)) {
+            continue;
+        }
+
+        unlisted += 1;
+        let path = path_link(repo_path);
+        println!("{}", format!("[?] {} => not in the manifest", path).cyan());
+    }
+
+    println!();
+    println!(
+        "Sync complete: {} cloned, {} with a different origin, {} not in the manifest, {} failed",
+        cloned, mismatched, unlisted, failed
+    );
+
+    if failed > 0 {
+        return Err(format!("{} manifest repositories could not be synced", failed).into());
+    }
+    Ok(())
+}
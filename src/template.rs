@@ -0,0 +1,424 @@
+use crate::display::hyperlink;
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Errors raised while parsing an output template
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("unclosed tag starting at offset {0}")]
+    UnclosedTag(usize),
+    #[error("empty tag at offset {0}")]
+    EmptyTag(usize),
+    #[error("unknown field '{0}'")]
+    UnknownField(String),
+    #[error("unknown filter '{0}'")]
+    UnknownFilter(String),
+    #[error("section '{0}' is never closed")]
+    UnclosedSection(String),
+    #[error("closing tag '{0}' does not match an open section")]
+    UnexpectedClose(String),
+}
+
+/// A value exposed to templates by a context
+pub enum Value<'a> {
+    Text(String),
+    Flag(bool),
+    Number(u64),
+    /// Text that becomes a terminal hyperlink when hyperlinks are enabled
+    Link {
+        text: String,
+        url: String,
+    },
+    List(Vec<Box<dyn TemplateContext + 'a>>),
+}
+
+impl Value<'_> {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Text(text) => !text.is_empty(),
+            Value::Flag(flag) => *flag,
+            Value::Number(number) => *number != 0,
+            Value::Link { text, .. } => !text.is_empty(),
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Value::Text(text) => text.clone(),
+            Value::Flag(flag) => flag.to_string(),
+            Value::Number(number) => number.to_string(),
+            Value::Link { text, .. } => text.clone(),
+            Value::List(items) => items.len().to_string(),
+        }
+    }
+}
+
+/// Something that can provide field values to a template
+pub trait TemplateContext {
+    fn field(&self, name: &str) -> Option<Value<'_>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Upper,
+    Lower,
+    Bold,
+    Dim,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Filter {
+    fn parse(name: &str) -> Result<Self, TemplateError> {
+        match name {
+            "upper" => Ok(Filter::Upper),
+            "lower" => Ok(Filter::Lower),
+            "bold" => Ok(Filter::Bold),
+            "dim" => Ok(Filter::Dim),
+            "red" => Ok(Filter::Red),
+            "green" => Ok(Filter::Green),
+            "yellow" => Ok(Filter::Yellow),
+            "blue" => Ok(Filter::Blue),
+            "magenta" => Ok(Filter::Magenta),
+            "cyan" => Ok(Filter::Cyan),
+            _ => Err(TemplateError::UnknownFilter(name.to_string())),
+        }
+    }
+
+    fn apply(&self, text: String) -> String {
+        match self {
+            Filter::Upper => text.to_uppercase(),
+            Filter::Lower => text.to_lowercase(),
+            Filter::Bold => text.bold().to_string(),
+            Filter::Dim => text.dimmed().to_string(),
+            Filter::Red => text.red().to_string(),
+            Filter::Green => text.green().to_string(),
+            Filter::Yellow => text.yellow().to_string(),
+            Filter::Blue => text.blue().to_string(),
+            Filter::Magenta => text.magenta().to_string(),
+            Filter::Cyan => text.cyan().to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Field {
+        name: String,
+        filters: Vec<Filter>,
+    },
+    Section {
+        name: String,
+        inverted: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// A parsed output template
+///
+/// Syntax:
+/// - `{field}` or `{field|filter|filter}` inserts a value
+/// - `{#name}...{/name}` renders the body once per list item, or once if the value is truthy
+/// - `{^name}...{/name}` renders the body only if the value is missing or falsy
+/// - `{{` and `}}` produce literal braces
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        // Stack of open sections: (name, inverted, nodes collected so far)
+        let mut stack: Vec<(String, bool, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+        let mut offset = 0;
+
+        while let Some(pos) = rest.find(['{', '}']) {
+            text.push_str(&rest[..pos]);
+            let tail = &rest[pos..];
+
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                text.push_str(&tail[..1]);
+                rest = &tail[2..];
+                offset += pos + 2;
+                continue;
+            }
+
+            if let Some(after) = tail.strip_prefix('}') {
+                text.push('}');
+                rest = after;
+                offset += pos + 1;
+                continue;
+            }
+
+            let end = tail
+                .find('}')
+                .ok_or(TemplateError::UnclosedTag(offset + pos))?;
+            let tag = tail[1..end].trim();
+            if tag.is_empty() {
+                return Err(TemplateError::EmptyTag(offset + pos));
+            }
+
+            if !text.is_empty() {
+                nodes.push(Node::Text(std::mem::take(&mut text)));
+            }
+
+            if let Some(name) = tag.strip_prefix('#') {
+                stack.push((name.trim().to_string(), false, std::mem::take(&mut nodes)));
+            } else if let Some(name) = tag.strip_prefix('^') {
+                stack.push((name.trim().to_string(), true, std::mem::take(&mut nodes)));
+            } else if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim();
+                match stack.pop() {
+                    Some((open, inverted, parent)) if open == name => {
+                        let body = std::mem::replace(&mut nodes, parent);
+                        nodes.push(Node::Section {
+                            name: open,
+                            inverted,
+                            body,
+                        });
+                    }
+                    _ => return Err(TemplateError::UnexpectedClose(name.to_string())),
+                }
+            } else {
+                let mut parts = tag.split('|').map(str::trim);
+                let name = parts.next().unwrap_or_default().to_string();
+                let filters = parts.map(Filter::parse).collect::<Result<Vec<_>, _>>()?;
+                nodes.push(Node::Field { name, filters });
+            }
+
+            rest = &tail[end + 1..];
+            offset += pos + end + 1;
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+
+        if let Some((name, _, _)) = stack.pop() {
+            return Err(TemplateError::UnclosedSection(name));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Check that every field and section refers to one of `fields`
+    pub fn validate(&self, fields: &[&str]) -> Result<(), TemplateError> {
+        validate_nodes(&self.nodes, fields)
+    }
+
+    /// Render the template against a context
+    pub fn render(&self, context: &dyn TemplateContext) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &[context], &mut out);
+        out
+    }
+}
+
+fn validate_nodes(nodes: &[Node], fields: &[&str]) -> Result<(), TemplateError> {
+    for node in nodes {
+        let (name, body) = match node {
+            Node::Text(_) => continue,
+            Node::Field { name, .. } => (name, None),
+            Node::Section { name, body, .. } => (name, Some(body)),
+        };
+
+        if !fields.contains(&name.as_str()) {
+            return Err(TemplateError::UnknownField(name.clone()));
+        }
+        if let Some(body) = body {
+            validate_nodes(body, fields)?;
+        }
+    }
+    Ok(())
+}
+
+/// Look a field up from the innermost scope outwards
+fn lookup<'a>(scopes: &[&'a dyn TemplateContext], name: &str) -> Option<Value<'a>> {
+    scopes.iter().rev().find_map(|scope| scope.field(name))
+}
+
+fn render_nodes(nodes: &[Node], scopes: &[&dyn TemplateContext], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Field { name, filters } => {
+                if let Some(value) = lookup(scopes, name) {
+                    let text = filters
+                        .iter()
+                        .fold(value.to_text(), |text, filter| filter.apply(text));
+                    match value {
+                        Value::Link { url, .. } => out.push_str(&hyperlink(&url, &text)),
+                        _ => out.push_str(&text),
+                    }
+                }
+            }
+            Node::Section {
+                name,
+                inverted,
+                body,
+            } => {
+                let value = lookup(scopes, name);
+                let truthy = value.as_ref().map(Value::is_truthy).unwrap_or(false);
+
+                if *inverted {
+                    if !truthy {
+                        render_nodes(body, scopes, out);
+                    }
+                    continue;
+                }
+
+                match value {
+                    Some(Value::List(items)) => {
+                        for item in &items {
+                            let mut item_scopes = scopes.to_vec();
+                            item_scopes.push(item.as_ref());
+                            render_nodes(body, &item_scopes, out);
+                        }
+                    }
+                    Some(_) if truthy => render_nodes(body, scopes, out),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Replace `\n`, `\t` and `\\` escapes in a template given on the command line
+fn unescape(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Load a template from an inline string, or from a file when prefixed with `@`
+///
+/// Fields are checked against `fields` so that typos fail before scanning.
+pub fn load_template(spec: &str, fields: &[&str]) -> Result<Template, Box<dyn std::error::Error>> {
+    let source = match spec.strip_prefix('@') {
+        Some(path) => fs::read_to_string(Path::new(path))?,
+        None => unescape(spec),
+    };
+
+    let template = Template::parse(&source)
+        .and_then(|template| template.validate(fields).map(|()| template))
+        .map_err(|e| format!("Invalid template: {}", e))?;
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Fields(HashMap<&'static str, &'static str>);
+
+    impl TemplateContext for Fields {
+        fn field(&self, name: &str) -> Option<Value<'_>> {
+            match name {
+                "items" => Some(Value::List(vec![
+                    Box::new(Fields(HashMap::from([("item", "a")]))),
+                    Box::new(Fields(HashMap::from([("item", "b")]))),
+                ])),
+                "none" => Some(Value::List(Vec::new())),
+                _ => self.0.get(name).map(|value| Value::Text(value.to_string())),
+            }
+        }
+    }
+
+    fn render(source: &str) -> String {
+        let context = Fields(HashMap::from([("name", "repo"), ("empty", "")]));
+        Template::parse(source).unwrap().render(&context)
+    }
+
+    #[test]
+    fn renders_fields_and_filters() {
+        assert_eq!(render("[{name}] {name|upper}"), "[repo] REPO");
+        assert_eq!(render("{{{name}}}"), "{repo}");
+    }
+
+    #[test]
+    fn renders_sections() {
+        assert_eq!(render("{#items}{item}-{name};{/items}"), "a-repo;b-repo;");
+        assert_eq!(render("{#none}x{/none}{^none}empty{/none}"), "empty");
+        assert_eq!(render("{#empty}x{/empty}{^empty}y{/empty}"), "y");
+        assert_eq!(render("{#name}has {name}{/name}"), "has repo");
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert!(matches!(
+            Template::parse("a {name"),
+            Err(TemplateError::UnclosedTag(2))
+        ));
+        assert!(matches!(
+            Template::parse("{ }"),
+            Err(TemplateError::EmptyTag(0))
+        ));
+        assert!(matches!(
+            Template::parse("{name|shout}"),
+            Err(TemplateError::UnknownFilter(filter)) if filter == "shout"
+        ));
+        assert!(matches!(
+            Template::parse("{#items}"),
+            Err(TemplateError::UnclosedSection(name)) if name == "items"
+        ));
+        assert!(matches!(
+            Template::parse("{#items}{/prs}"),
+            Err(TemplateError::UnexpectedClose(name)) if name == "prs"
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let fields = ["path", "branch", "prs", "title"];
+        let validate = |source: &str| Template::parse(source).unwrap().validate(&fields);
+
+        assert!(validate("{path} {branch|upper} {#prs}{title}{/prs}").is_ok());
+        assert!(matches!(
+            validate("{path} {brnach}"),
+            Err(TemplateError::UnknownField(name)) if name == "brnach"
+        ));
+        assert!(matches!(
+            validate("{^pr}none{/pr}"),
+            Err(TemplateError::UnknownField(name)) if name == "pr"
+        ));
+        assert!(matches!(
+            validate("{#prs}{titel}{/prs}"),
+            Err(TemplateError::UnknownField(name)) if name == "titel"
+        ));
+    }
+
+    #[test]
+    fn unescapes_command_line_templates() {
+        assert_eq!(unescape(r"a\nb\tc\\d\x"), "a\nb\tc\\d\\x");
+        assert_eq!(unescape(r"end\"), "end\\");
+    }
+}
@@ -0,0 +1,202 @@
+use crate::git::details::{load_repo_details, RepoDetails};
+use crate::repo::filters::RepoFilters;
+use crate::repo::status::RepoStatus;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::widgets::ListState;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// What the event loop has to do after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Quit,
+    OpenShell,
+    Fetch,
+    Rescan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Search,
+}
+
+pub struct App {
+    pub root: PathBuf,
+    pub filters: RepoFilters,
+    pub repos: Vec<(PathBuf, RepoStatus)>,
+    /// Indices into `repos` that pass the current search and issue filter
+    pub visible: Vec<usize>,
+    pub list_state: ListState,
+    pub mode: Mode,
+    pub search: String,
+    pub issues_only: bool,
+    pub message: Option<String>,
+    details: HashMap<PathBuf, RepoDetails>,
+}
+
+impl App {
+    pub fn new(root: PathBuf, filters: RepoFilters, repos: Vec<(PathBuf, RepoStatus)>) -> Self {
+        let mut app = Self {
+            root,
+            filters,
+            repos,
+            visible: Vec::new(),
+            list_state: ListState::default(),
+            mode: Mode::Normal,
+            search: String::new(),
+            issues_only: false,
+            message: None,
+            details: HashMap::new(),
+        };
+        app.refresh_visible();
+        app
+    }
+
+    /// Name shown in the list, relative to the scan root when possible
+    pub fn display_name<'a>(&self, path: &'a Path) -> std::borrow::Cow<'a, str> {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+    }
+
+    pub fn selected(&self) -> Option<&(PathBuf, RepoStatus)> {
+        let index = *self.visible.get(self.list_state.selected()?)?;
+        self.repos.get(index)
+    }
+
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.selected().map(|(path, _)| path.clone())
+    }
+
+    /// Details for the selected repository, loaded on first access
+    pub fn selected_details(&mut self) -> Option<&RepoDetails> {
+        let path = self.selected_path()?;
+        Some(
+            self.details
+                .entry(path.clone())
+                .or_insert_with(|| load_repo_details(&path)),
+        )
+    }
+
+    /// Replace the status of a repository after it was re-scanned
+    pub fn update_repo(&mut self, path: &Path, status: RepoStatus) {
+        if let Some(entry) = self.repos.iter_mut().find(|(p, _)| p == path) {
+            entry.1 = status;
+        }
+        self.details.remove(path);
+        self.refresh_visible();
+    }
+
+    pub fn refresh_visible(&mut self) {
+        let selected = self.selected_path();
+        let needle = self.search.to_lowercase();
+
+        self.visible = self
+            .repos
+            .iter()
+            .enumerate()
+            .filter(|(_, (path, status))| {
+                (!self.issues_only || status.has_issues(&self.filters))
+                    && (needle.is_empty()
+                        || self.display_name(path).to_lowercase().contains(&needle))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        // Keep the same repository selected if it is still visible
+        let position = selected
+            .and_then(|selected| {
+                self.visible
+                    .iter()
+                    .position(|index| self.repos[*index].0 == selected)
+            })
+            .or(if self.visible.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+        self.list_state.select(position);
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        self.list_state
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Action::Quit;
+        }
+
+        match self.mode {
+            Mode::Search => {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Normal,
+                    KeyCode::Backspace => {
+                        self.search.pop();
+                        self.refresh_visible();
+                    }
+                    KeyCode::Char(c) => {
+                        self.search.push(c);
+                        self.refresh_visible();
+                    }
+                    _ => {}
+                }
+                Action::None
+            }
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') => Action::Quit,
+                KeyCode::Esc if !self.search.is_empty() => {
+                    self.search.clear();
+                    self.refresh_visible();
+                    Action::None
+                }
+                KeyCode::Esc => Action::Quit,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.move_selection(1);
+                    Action::None
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.move_selection(-1);
+                    Action::None
+                }
+                KeyCode::PageDown => {
+                    self.move_selection(10);
+                    Action::None
+                }
+                KeyCode::PageUp => {
+                    self.move_selection(-10);
+                    Action::None
+                }
+                KeyCode::Home | KeyCode::Char('g') => {
+                    self.move_selection(isize::MIN / 2);
+                    Action::None
+                }
+                KeyCode::End | KeyCode::Char('G') => {
+                    self.move_selection(isize::MAX / 2);
+                    Action::None
+                }
+                KeyCode::Char('/') => {
+                    self.mode = Mode::Search;
+                    Action::None
+                }
+                KeyCode::Char('i') => {
+                    self.issues_only = !self.issues_only;
+                    self.refresh_visible();
+                    Action::None
+                }
+                KeyCode::Char('s') | KeyCode::Enter => Action::OpenShell,
+                KeyCode::Char('f') => Action::Fetch,
+                KeyCode::Char('r') => Action::Rescan,
+                _ => Action::None,
+            },
+        }
+    }
+}
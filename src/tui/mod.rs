@@ -0,0 +1,109 @@
+mod app;
+mod ui;
+
+use crate::git::remote::fetch_remotes_unattended;
+use crate::repo::scan::{rescan_repo, scan_repos, ScanOptions};
+use app::{Action, App};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Scan `repos` and browse the results in an interactive terminal UI
+pub async fn run_tui(
+    root: PathBuf,
+    repos: Vec<PathBuf>,
+    mut options: ScanOptions,
+    fetch_timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Debug output would be drawn over the UI
+    options.debug = false;
+
+    println!("Scanning {} repositories...", repos.len());
+    let results = scan_repos(&repos, &options).await?;
+    let mut app = App::new(root, options.filters.clone(), results);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &options, fetch_timeout).await;
+    ratatui::restore();
+
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    options: &ScanOptions,
+    fetch_timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        app.message = None;
+        let action = app.handle_key(key);
+        let Some(path) = app.selected_path() else {
+            if action == Action::Quit {
+                return Ok(());
+            }
+            continue;
+        };
+
+        match action {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::OpenShell => {
+                ratatui::restore();
+                let shell_result = open_shell(&path);
+                *terminal = ratatui::init();
+
+                // The user probably changed something in there
+                let status = rescan_repo(&path, options).await?;
+                app.update_repo(&path, status);
+                app.message = shell_result.err();
+            }
+            Action::Fetch => {
+                app.message = Some(format!("Fetching {}...", app.display_name(&path)));
+                terminal.draw(|frame| ui::draw(frame, app))?;
+
+                let fetch_result = fetch_remotes_unattended(&path, fetch_timeout).await;
+                let status = rescan_repo(&path, options).await?;
+                app.update_repo(&path, status);
+                app.message = Some(match fetch_result {
+                    Ok(()) => format!("Fetched {}", app.display_name(&path)),
+                    Err(e) => format!("Fetch failed: {}", e),
+                });
+            }
+            Action::Rescan => {
+                let status = rescan_repo(&path, options).await?;
+                app.update_repo(&path, status);
+                app.message = Some(format!("Re-scanned {}", app.display_name(&path)));
+            }
+        }
+    }
+}
+
+/// Run the user's shell inside the repository until it exits
+fn open_shell(repo_path: &Path) -> Result<(), String> {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    println!(
+        "Opening {} in {} (exit to return)",
+        shell,
+        repo_path.display()
+    );
+
+    Command::new(&shell)
+        .current_dir(repo_path)
+        .status()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start {}: {}", shell, e))
+}
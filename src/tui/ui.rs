@@ -0,0 +1,149 @@
+use crate::repo::findings::CheckKind;
+use crate::tui::app::{App, Mode};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph, Wrap};
+use ratatui::Frame;
+
+fn check_color(check: CheckKind) -> Color {
+    match check {
+        CheckKind::Untracked => Color::Green,
+        CheckKind::Unstaged | CheckKind::RateLimited => Color::Yellow,
+        CheckKind::Ahead
+        | CheckKind::NoRemote
+        | CheckKind::FetchFailed
+        | CheckKind::RemoteHealth => Color::Red,
+        CheckKind::Branch => Color::Cyan,
+        CheckKind::OpenPrs | CheckKind::UpstreamPrs => Color::Blue,
+        CheckKind::Misplaced | CheckKind::BehindUpstream | CheckKind::MergedUpstream => {
+            Color::Magenta
+        }
+    }
+}
+
+fn section<'a>(lines: &mut Vec<Line<'a>>, title: &'a str, entries: &[String], empty: &'a str) {
+    lines.push(Line::default());
+    lines.push(Line::from(title.bold()));
+    if entries.is_empty() {
+        lines.push(Line::from(format!("  {}", empty).dark_gray()));
+    }
+    for entry in entries {
+        lines.push(Line::from(format!("  {}", entry)));
+    }
+}
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [list_area, detail_area] =
+        Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(main);
+
+    // Repository list
+    let items: Vec<ListItem> = app
+        .visible
+        .iter()
+        .map(|index| {
+            let (path, status) = &app.repos[*index];
+            let findings = status.findings(&app.filters);
+            let marker = if findings.is_empty() {
+                Span::styled("  ", Style::default())
+            } else {
+                Span::styled("! ", Style::default().fg(Color::Red).bold())
+            };
+            let mut spans = vec![marker, Span::raw(app.display_name(path).to_string())];
+            for finding in findings {
+                spans.push(Span::styled(
+                    format!(" [{}]", finding.check.id()),
+                    Style::default().fg(check_color(finding.check)),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let mut title = format!(" Repositories ({}/{}) ", app.visible.len(), app.repos.len());
+    if app.issues_only {
+        title.push_str("[issues only] ");
+    }
+    let list = List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, &mut app.list_state);
+
+    // Detail pane for the selected repository
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some((path, status)) = app.selected().cloned() {
+        lines.push(Line::from(vec![
+            "Path:   ".bold(),
+            Span::raw(path.display().to_string()),
+        ]));
+        lines.push(Line::from(vec![
+            "Branch: ".bold(),
+            Span::raw(status.current_branch.clone()),
+        ]));
+
+        lines.push(Line::default());
+        lines.push(Line::from("Findings".bold()));
+        let findings = status.findings(&app.filters);
+        if findings.is_empty() {
+            lines.push(Line::from("  none".dark_gray()));
+        }
+        for finding in findings {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", finding.detail),
+                Style::default().fg(check_color(finding.check)),
+            )));
+        }
+
+        if let Some(details) = app.selected_details() {
+            let details = details.clone();
+            section(&mut lines, "Files", &details.files, "clean");
+            section(&mut lines, "Branches", &details.branches, "none");
+            section(&mut lines, "Stashes", &details.stashes, "none");
+            section(&mut lines, "Remotes", &details.remotes, "none");
+        }
+
+        let prs: Vec<String> = status
+            .open_prs
+            .iter()
+            .map(|pr| {
+                let draft_marker = if pr.is_draft { "[DRAFT] " } else { "" };
+                let status_marker = pr
+                    .status_summary()
+                    .map(|summary| format!(" [{}]", summary))
+                    .unwrap_or_default();
+                format!(
+                    "#{} {}{} ({}){}",
+                    pr.number, draft_marker, pr.title, pr.branch, status_marker
+                )
+            })
+            .collect();
+        let empty = if app.filters.check_prs {
+            "none"
+        } else {
+            "not checked (enable --check-prs)"
+        };
+        section(&mut lines, "Pull requests", &prs, empty);
+    } else {
+        lines.push(Line::from("No repository selected".dark_gray()));
+    }
+
+    let details = Paragraph::new(lines)
+        .block(Block::bordered().title(" Details "))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(details, detail_area);
+
+    // Footer with search prompt, last message or key help
+    let footer_line = match app.mode {
+        Mode::Search => Line::from(vec!["/".bold(), Span::raw(app.search.clone())]),
+        Mode::Normal => match &app.message {
+            Some(message) => Line::from(message.clone().yellow()),
+            None => Line::from(
+                " q quit  j/k move  / search  i issues only  s shell  f fetch  r re-scan"
+                    .dark_gray(),
+            ),
+        },
+    };
+    frame.render_widget(Paragraph::new(footer_line), footer);
+}
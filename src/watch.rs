@@ -0,0 +1,507 @@
+use crate::display::display_repos_status;
+use crate::repo::scan::{discover_workspace, scan_repos, ScanOptions};
+use crate::repo::status::RepoStatus;
+use colored::Colorize;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+/// How `gitgud watch` reports changes
+#[derive(Debug, Clone, Copy)]
+pub struct WatchOptions {
+    /// Emit NDJSON change events instead of redrawing the report
+    pub ndjson: bool,
+    /// Quiet period to wait for before re-scanning
+    pub debounce: Duration,
+    /// Show repositories without issues in the redrawn report
+    pub show_all: bool,
+}
+
+/// Directories git ignores in a repository, relative to it
+fn ignored_dirs(repo_path: &Path) -> BTreeSet<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args([
+            "ls-files",
+            "-z",
+            "--others",
+            "--ignored",
+            "--exclude-standard",
+            "--directory",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+
+    output
+        .map(|output| {
+            output
+                .stdout
+                .split(|byte| *byte == 0)
+                .filter_map(|entry| std::str::from_utf8(entry).ok())
+                .filter_map(|entry| entry.strip_suffix('/'))
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collect the directories below `dir`, leaving out ignored ones and nested repositories
+fn walk_worktree(
+    repo_path: &Path,
+    dir: &Path,
+    ignored: &BTreeSet<PathBuf>,
+    dirs: &mut BTreeMap<PathBuf, RecursiveMode>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if !entry.file_type().is_ok_and(|t| t.is_dir())
+            || entry.file_name() == ".git"
+            || path.join(".git").exists()
+            || path
+                .strip_prefix(repo_path)
+                .is_ok_and(|relative| ignored.contains(relative))
+        {
+            continue;
+        }
+        dirs.insert(path.clone(), RecursiveMode::NonRecursive);
+        walk_worktree(repo_path, &path, ignored, dirs);
+    }
+}
+
+/// Directories to watch in a repository
+///
+/// `.git` itself for the index and HEAD, its refs for commits and stashes, and
+/// every worktree directory git does not ignore. Ignored build output and
+/// dependencies are left alone, they can hold more directories than the
+/// inotify watch limit allows.
+fn watched_dirs(repo_path: &Path) -> BTreeMap<PathBuf, RecursiveMode> {
+    let mut dirs = BTreeMap::from([
+        (repo_path.to_path_buf(), RecursiveMode::NonRecursive),
+        (repo_path.join(".git"), RecursiveMode::NonRecursive),
+        (
+            repo_path.join(".git").join("refs"),
+            RecursiveMode::Recursive,
+        ),
+    ]);
+    walk_worktree(repo_path, repo_path, &ignored_dirs(repo_path), &mut dirs);
+    dirs
+}
+
+/// Bring the watches of a repository in line with its directories, returns what is watched
+///
+/// Failing to watch is always reported, running into the inotify watch limit
+/// would otherwise go unnoticed while changes are missed.
+fn watch_repo(
+    watcher: &mut RecommendedWatcher,
+    repo_path: &Path,
+    watched: Option<&BTreeMap<PathBuf, RecursiveMode>>,
+) -> BTreeMap<PathBuf, RecursiveMode> {
+    let dirs = watched_dirs(repo_path);
+    if let Some(watched) = watched {
+        for dir in watched.keys().filter(|dir| !dirs.contains_key(*dir)) {
+            let _ = watcher.unwatch(dir);
+        }
+    }
+
+    let mut failed = 0;
+    for (dir, mode) in &dirs {
+        if watched.is_some_and(|watched| watched.contains_key(dir)) {
+            continue;
+        }
+        if let Err(e) = watcher.watch(dir, *mode) {
+            if failed == 0 {
+                eprintln!(
+                    "{}",
+                    format!("[!] Unable to watch {}: {}", dir.display(), e)
+                        .yellow()
+                        .bold()
+                );
+            }
+            failed += 1;
+        }
+    }
+    if failed > 1 {
+        eprintln!(
+            "{}",
+            format!(
+                "[!] {} directories of {} are not watched, changes there are missed",
+                failed,
+                repo_path.display()
+            )
+            .yellow()
+            .bold()
+        );
+    }
+    dirs
+}
+
+/// Watch the directories between `root` and each repository, to notice
+/// repositories being added or removed in nested layouts
+fn watch_layout_dirs(
+    watcher: &mut RecommendedWatcher,
+    root: &Path,
+    repos: &[PathBuf],
+    watched: &mut BTreeSet<PathBuf>,
+) {
+    for repo_path in repos {
+        let Ok(relative) = repo_path.strip_prefix(root) else {
+            continue;
+        };
+        for dir in relative.ancestors().skip(1) {
+            if dir.as_os_str().is_empty() || watched.contains(&root.join(dir)) {
+                continue;
+            }
+            let dir = root.join(dir);
+            if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                eprintln!(
+                    "{}",
+                    format!("[!] Unable to watch {}: {}", dir.display(), e)
+                        .yellow()
+                        .bold()
+                );
+            }
+            watched.insert(dir);
+        }
+    }
+}
+
+/// `path` relative to `repo_path`, events carry absolute paths even for a relative root
+fn path_in_repo(path: &Path, repo_path: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(repo_path) {
+        return Some(relative.to_path_buf());
+    }
+    let absolute = std::path::absolute(repo_path).ok()?;
+    path.strip_prefix(absolute).ok().map(Path::to_path_buf)
+}
+
+/// Whether a change below `.git` can affect the checks
+///
+/// Objects, reflogs and lock files churn on every git command, including our own
+/// scans, without changing anything that is checked.
+fn git_dir_change_matters(path_in_git: &Path) -> bool {
+    !path_in_git.starts_with("objects")
+        && !path_in_git.starts_with("logs")
+        && path_in_git.extension().is_none_or(|ext| ext != "lock")
+}
+
+/// Whether any of the changed worktree `paths` is not ignored by git
+fn has_unignored_changes(repo_path: &Path, paths: &[PathBuf]) -> bool {
+    let child = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["check-ignore", "--stdin", "-z"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return true;
+    };
+
+    let mut input = Vec::new();
+    for path in paths {
+        input.extend_from_slice(path.to_string_lossy().as_bytes());
+        input.push(0);
+    }
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(&input);
+    }
+
+    // Exits with 1 when nothing is ignored, anything above that is an error
+    match child.wait_with_output() {
+        Ok(output) if output.status.code() == Some(0) => {
+            let ignored = output
+                .stdout
+                .split(|byte| *byte == 0)
+                .filter(|path| !path.is_empty());
+            ignored.count() < paths.len()
+        }
+        _ => true,
+    }
+}
+
+fn emit_event(event: &str, repo_path: &Path, status: Option<&RepoStatus>, options: &ScanOptions) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let findings: Vec<_> = status
+        .map(|status| status.findings(&options.filters))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|finding| json!({ "check": finding.check.id(), "detail": finding.detail }))
+        .collect();
+
+    let record = json!({
+        "event": event,
+        "repo": repo_path.display().to_string(),
+        "has_issues": !findings.is_empty(),
+        "findings": findings,
+        "timestamp": timestamp,
+    });
+    println!("{}", record);
+}
+
+fn redraw(
+    state: &BTreeMap<PathBuf, RepoStatus>,
+    root: &Path,
+    options: &ScanOptions,
+    watch: &WatchOptions,
+) {
+    let results: Vec<(PathBuf, RepoStatus)> = state
+        .iter()
+        .filter(|(_, status)| watch.show_all || status.has_issues(&options.filters))
+        .map(|(path, status)| (path.clone(), status.clone()))
+        .collect();
+
+    // Clear the screen and move the cursor home
+    print!("\x1b[2J\x1b[H");
+    display_repos_status(&results, &options.filters);
+    println!(
+        "Watching {} repositories in {} ({} with issues), press Ctrl-C to stop",
+        state.len(),
+        root.display(),
+        state
+            .values()
+            .filter(|status| status.has_issues(&options.filters))
+            .count()
+    );
+}
+
+/// Scan once, then re-scan repositories whenever something in them changes
+pub async fn run_watch(
+    root: PathBuf,
+    options: ScanOptions,
+    watch: WatchOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let debug = options.debug;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+
+    // The root itself is watched to notice repositories being added or removed
+    watcher.watch(&root, RecursiveMode::NonRecursive)?;
+
+    let repos = discover_workspace(&root, &options.filters)?;
+    let mut layout_dirs = BTreeSet::new();
+    watch_layout_dirs(&mut watcher, &root, &repos, &mut layout_dirs);
+    let mut watches: BTreeMap<PathBuf, BTreeMap<PathBuf, RecursiveMode>> = repos
+        .iter()
+        .map(|repo_path| (repo_path.clone(), watch_repo(&mut watcher, repo_path, None)))
+        .collect();
+
+    let mut state: BTreeMap<PathBuf, RepoStatus> =
+        scan_repos(&repos, &options).await?.into_iter().collect();
+
+    if watch.ndjson {
+        for (repo_path, status) in &state {
+            emit_event("scanned", repo_path, Some(status), &options);
+        }
+    } else {
+        redraw(&state, &root, &options, &watch);
+    }
+
+    while let Some(first) = rx.recv().await {
+        // Collect everything that arrives until things calm down
+        let mut events = vec![first];
+        while let Ok(Some(event)) = tokio::time::timeout(watch.debounce, rx.recv()).await {
+            events.push(event);
+        }
+
+        let mut changed = BTreeSet::new();
+        let mut worktree_changes: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        let mut rediscover = false;
+
+        for event in events.into_iter().filter_map(Result::ok) {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            for path in event.paths {
+                let Some((repo_path, relative)) = state
+                    .keys()
+                    .find_map(|repo_path| Some((repo_path, path_in_repo(&path, repo_path)?)))
+                else {
+                    rediscover = true;
+                    continue;
+                };
+
+                if let Ok(path_in_git) = relative.strip_prefix(".git") {
+                    if git_dir_change_matters(path_in_git) {
+                        changed.insert(repo_path.clone());
+                    }
+                } else if relative.as_os_str().is_empty() {
+                    changed.insert(repo_path.clone());
+                } else {
+                    worktree_changes
+                        .entry(repo_path.clone())
+                        .or_default()
+                        .push(relative.to_path_buf());
+                }
+            }
+        }
+
+        // Build output and dependencies in ignored directories are no news
+        for (repo_path, paths) in worktree_changes {
+            if !changed.contains(&repo_path) && has_unignored_changes(&repo_path, &paths) {
+                changed.insert(repo_path);
+            }
+        }
+
+        // A repository that was moved away only shows up as a change inside it
+        if changed
+            .iter()
+            .any(|repo_path| !repo_path.join(".git").is_dir())
+        {
+            rediscover = true;
+        }
+
+        if rediscover {
+            let discovered: BTreeSet<PathBuf> = discover_workspace(&root, &options.filters)?
+                .into_iter()
+                .collect();
+
+            let removed: Vec<PathBuf> = state
+                .keys()
+                .filter(|repo_path| !discovered.contains(*repo_path))
+                .cloned()
+                .collect();
+            for repo_path in removed {
+                for dir in watches.remove(&repo_path).unwrap_or_default().keys() {
+                    let _ = watcher.unwatch(dir);
+                }
+                state.remove(&repo_path);
+                changed.remove(&repo_path);
+                if watch.ndjson {
+                    emit_event("removed", &repo_path, None, &options);
+                }
+            }
+
+            let added: Vec<PathBuf> = discovered
+                .into_iter()
+                .filter(|repo_path| !state.contains_key(repo_path))
+                .collect();
+            watch_layout_dirs(&mut watcher, &root, &added, &mut layout_dirs);
+            for repo_path in &added {
+                let watched = watch_repo(&mut watcher, repo_path, None);
+                watches.insert(repo_path.clone(), watched);
+            }
+            for (repo_path, status) in scan_repos(&added, &options).await? {
+                if watch.ndjson {
+                    emit_event("added", &repo_path, Some(&status), &options);
+                }
+                state.insert(repo_path, status);
+            }
+        }
+
+        // Re-checked together, so pull requests are looked up like in a full scan
+        let changed: Vec<PathBuf> = changed.into_iter().collect();
+        if debug {
+            for repo_path in &changed {
+                println!("[-] Change detected in {}", repo_path.display());
+            }
+        }
+
+        // New directories need watches of their own
+        for repo_path in &changed {
+            let watched = watch_repo(&mut watcher, repo_path, watches.get(repo_path));
+            watches.insert(repo_path.clone(), watched);
+        }
+
+        for (repo_path, status) in scan_repos(&changed, &options).await? {
+            let previous = state.insert(repo_path.clone(), status.clone());
+            let findings_changed = previous
+                .map(|previous| previous.findings(&options.filters))
+                .unwrap_or_default()
+                != status.findings(&options.filters);
+
+            if watch.ndjson && findings_changed {
+                emit_event("changed", &repo_path, Some(&status), &options);
+            }
+        }
+
+        if !watch.ndjson {
+            redraw(&state, &root, &options, &watch);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_dir_churn_is_ignored() {
+        assert!(git_dir_change_matters(Path::new("index")));
+        assert!(git_dir_change_matters(Path::new("HEAD")));
+        assert!(git_dir_change_matters(Path::new("refs/heads/main")));
+        assert!(!git_dir_change_matters(Path::new("index.lock")));
+        assert!(!git_dir_change_matters(Path::new("refs/heads/main.lock")));
+        assert!(!git_dir_change_matters(Path::new("objects/ab/cdef")));
+        assert!(!git_dir_change_matters(Path::new("logs/HEAD")));
+    }
+
+    #[test]
+    fn event_paths_match_relative_repositories() {
+        let absolute = std::env::current_dir().unwrap().join("ws/repo/src/main.rs");
+        assert_eq!(
+            path_in_repo(&absolute, Path::new("ws/repo")),
+            Some(PathBuf::from("src/main.rs"))
+        );
+        assert_eq!(
+            path_in_repo(Path::new("ws/repo/.git/HEAD"), Path::new("ws/repo")),
+            Some(PathBuf::from(".git/HEAD"))
+        );
+        assert_eq!(path_in_repo(&absolute, Path::new("ws/other")), None);
+    }
+
+    #[test]
+    fn ignored_directories_are_not_watched() {
+        let repo = std::env::temp_dir().join(format!("gitgud-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&repo);
+        for dir in ["src/bin", "notes", "empty", "target/debug"] {
+            fs::create_dir_all(repo.join(dir)).unwrap();
+        }
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["init", "--quiet"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+        fs::write(repo.join("src/bin/main.rs"), "").unwrap();
+        fs::write(repo.join("notes/todo.txt"), "").unwrap();
+        fs::write(repo.join("target/debug/gitgud"), "").unwrap();
+
+        let dirs = watched_dirs(&repo);
+        let relative: Vec<&Path> = dirs
+            .keys()
+            .map(|dir| dir.strip_prefix(&repo).unwrap())
+            .collect();
+        assert_eq!(
+            relative,
+            ["", ".git", ".git/refs", "empty", "notes", "src", "src/bin"]
+                .map(Path::new)
+                .to_vec()
+        );
+        assert_eq!(dirs[&repo.join(".git/refs")], RecursiveMode::Recursive);
+        assert_eq!(dirs[&repo.join("src")], RecursiveMode::NonRecursive);
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "gitgud")
+        .env("GIT_AUTHOR_EMAIL", "gitgud@example.com")
+        .env("GIT_COMMITTER_NAME", "gitgud")
+        .env("GIT_COMMITTER_EMAIL", "gitgud@example.com")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Run gitgud from `cwd` with a throwaway home so no user config or state is used
+fn gitgud(cwd: &Path, home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gitgud"))
+        .current_dir(cwd)
+        .args(args)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("XDG_CACHE_HOME", home.join(".cache"))
+        .env("XDG_DATA_HOME", home.join(".local/share"))
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gitgud-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn backup_and_restore_round_trip_with_relative_paths() {
+    let tmp = scratch_dir("backup");
+    let home = tmp.join("home");
+    fs::create_dir_all(&home).unwrap();
+
+    // A repository with a remote, plus unpushed, stashed, uncommitted and untracked work
+    let seed = tmp.join("seed");
+    fs::create_dir_all(&seed).unwrap();
+    git(&seed, &["init", "--quiet"]);
+    fs::write(seed.join("README"), "hello\n").unwrap();
+    git(&seed, &["add", "README"]);
+    git(&seed, &["commit", "--quiet", "-m", "initial"]);
+    git(&tmp, &["clone", "--quiet", "--bare", "seed", "origin.git"]);
+
+    fs::create_dir_all(tmp.join("work")).unwrap();
+    git(&tmp, &["clone", "--quiet", "origin.git", "work/project"]);
+    let project = tmp.join("work/project");
+    fs::write(project.join("README"), "hello\nunpushed\n").unwrap();
+    git(&project, &["commit", "--quiet", "-am", "unpushed"]);
+    let unpushed = git(&project, &["rev-parse", "HEAD"]);
+    fs::write(project.join("README"), "hello\nunpushed\nstashed\n").unwrap();
+    git(&project, &["stash", "--quiet"]);
+    fs::write(project.join("README"), "hello\nunpushed\nuncommitted\n").unwrap();
+    fs::write(project.join("notes.txt"), "untracked\n").unwrap();
+
+    let output = gitgud(&tmp, &home, &["work", "backup", "backups"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let backup = fs::read_dir(tmp.join("backups"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .file_name();
+    let backup = Path::new("backups").join(backup);
+
+    let output = gitgud(
+        &tmp,
+        &home,
+        &["restore", backup.to_str().unwrap(), "--into", "restored"],
+    );
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let restored = tmp.join("restored/project");
+    assert_eq!(git(&restored, &["rev-parse", "HEAD"]), unpushed);
+    assert_eq!(git(&restored, &["stash", "list"]).lines().count(), 1);
+    assert_eq!(
+        fs::read_to_string(restored.join("README")).unwrap(),
+        "hello\nunpushed\nuncommitted\n"
+    );
+    assert_eq!(
+        fs::read_to_string(restored.join("notes.txt")).unwrap(),
+        "untracked\n"
+    );
+
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+#[test]
+fn partial_restore_exits_non_zero() {
+    let tmp = scratch_dir("restore-partial");
+    let home = tmp.join("home");
+    fs::create_dir_all(&home).unwrap();
+
+    let project = tmp.join("work/project");
+    fs::create_dir_all(&project).unwrap();
+    git(&project, &["init", "--quiet"]);
+    fs::write(project.join("README"), "hello\n").unwrap();
+    git(&project, &["add", "README"]);
+    git(&project, &["commit", "--quiet", "-m", "initial"]);
+    fs::write(project.join("README"), "hello\nuncommitted\n").unwrap();
+
+    let output = gitgud(&tmp, &home, &["work", "backup", "backups"]);
+    assert!(output.status.success());
+
+    // The patch no longer applies once the file changed in the target
+    fs::write(project.join("README"), "something else\n").unwrap();
+    let backup = fs::read_dir(tmp.join("backups"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let manifest = fs::read_to_string(backup.join("manifest.json")).unwrap();
+    assert!(manifest.contains("uncommitted.patch"));
+
+    let output = gitgud(&tmp, &home, &["restore", backup.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("partially restored"));
+
+    fs::remove_dir_all(&tmp).unwrap();
+}